@@ -0,0 +1,22 @@
+#!/bin/sh
+# Refreshes data/chains.json, the chainid.network list embedded in the binary
+# and served until the first refresh at runtime succeeds.
+set -eu
+
+CHAIN_LIST_URL="https://chainid.network/chains.json"
+TARGET="$(dirname "$0")/../data/chains.json"
+
+tmp="$(mktemp)"
+trap 'rm -f "$tmp"' EXIT
+
+curl --fail --silent --show-error --location --max-time 60 "$CHAIN_LIST_URL" -o "$tmp"
+
+# Refuse to replace the snapshot with anything but a non-empty JSON array.
+if [ "$(head -c 1 "$tmp")" != "[" ] || [ "$(wc -c < "$tmp")" -lt 100000 ]; then
+    echo "Unexpected response from $CHAIN_LIST_URL, keeping $TARGET" >&2
+    exit 1
+fi
+
+mv "$tmp" "$TARGET"
+trap - EXIT
+echo "Updated $TARGET"
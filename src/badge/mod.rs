@@ -6,6 +6,7 @@ use crate::data_source::SourceResponseWithMetadata;
 #[derive(Debug)]
 pub enum Logo {
     Slug(String),
+    Svg(String),
 }
 
 pub struct Badge {
@@ -16,6 +17,8 @@ pub struct Badge {
     pub message: String,
     pub suffix: Option<String>,
     pub is_error: bool,
+    /// How long shields.io may cache the badge for.
+    pub cache_seconds: Option<u64>,
 }
 
 impl Badge {
@@ -28,8 +31,20 @@ impl Badge {
             message: message.to_string(),
             suffix: None,
             is_error: false,
+            cache_seconds: None,
         }
     }
+
+    /// Greys the badge out and marks it as stale, after the suffix, so that
+    /// an outdated value is not mistaken for a current one.
+    pub fn mark_stale(&mut self) {
+        if let Some(suffix) = self.suffix.take() {
+            self.message = format!("{} {suffix}", self.message);
+        }
+
+        self.message = format!("{} (stale)", self.message);
+        self.color = Some("lightgrey".to_string());
+    }
 }
 
 impl From<&SourceResponseWithMetadata> for Badge {
@@ -38,7 +53,7 @@ impl From<&SourceResponseWithMetadata> for Badge {
 
         badge.label = value.metadata.label();
         badge.icon = value.metadata.logo().map(Logo::Slug);
-        badge.suffix = Some(value.metadata.symbol());
+        badge.suffix = value.metadata.symbol();
 
         badge
     }
@@ -1,3 +1,5 @@
+use base64::{prelude::BASE64_STANDARD, Engine};
+
 use super::{Badge, Logo};
 
 const BASE_URL: &str = "https://img.shields.io/badge";
@@ -20,8 +22,8 @@ impl From<Badge> for ShildsIoBadge {
             "{BASE_URL}/{}-{}{}-{}",
             label,
             value.message,
-            if value.suffix.is_some() {
-                format!(" {}", value.suffix.unwrap())
+            if let Some(suffix) = &value.suffix {
+                format!(" {}", suffix)
             } else {
                 "".to_string()
             },
@@ -57,5 +59,6 @@ impl From<Badge> for ShildsIoBadge {
 fn from_badge_logo(logo: &Logo) -> String {
     match logo {
         Logo::Slug(slug) => slug.to_string(),
+        Logo::Svg(svg) => format!("data:image/svg+xml;base64,{}", BASE64_STANDARD.encode(svg)),
     }
 }
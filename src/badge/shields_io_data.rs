@@ -25,6 +25,8 @@ pub struct ShildsIoBadgeData {
     logo_width: Option<String>,
     #[serde(skip_serializing_if = "Option::is_none")]
     style: Option<String>,
+    #[serde(rename = "cacheSeconds", skip_serializing_if = "Option::is_none")]
+    cache_seconds: Option<u64>,
 }
 
 impl From<Badge> for ShildsIoBadgeData {
@@ -42,18 +44,19 @@ impl From<Badge> for ShildsIoBadgeData {
             color: Some(value.color.unwrap_or("blue".to_string())),
             label_color: value.label_color,
             is_error: value.is_error,
-            named_logo: value.icon.map(from_badge_logo),
-            logo_svg: None,
+            named_logo: match &value.icon {
+                Some(Logo::Slug(slug)) => Some(slug.to_string()),
+                _ => None,
+            },
+            logo_svg: match value.icon {
+                Some(Logo::Svg(svg)) => Some(svg),
+                _ => None,
+            },
             logo_color: None,
             logo_size: None,
             logo_width: None,
             style: None,
+            cache_seconds: value.cache_seconds,
         }
     }
 }
-
-fn from_badge_logo(logo: Logo) -> String {
-    match logo {
-        Logo::Slug(slug) => slug.to_string(),
-    }
-}
@@ -0,0 +1,233 @@
+use std::{collections::HashMap, error::Error, path::PathBuf, str::FromStr};
+
+use serde::Deserialize;
+
+use crate::types::EvmAddress;
+
+const CONFIG_PATH_ENV: &str = "BADGIFY_CONFIG";
+
+/// Operator configuration, read from the TOML file named by `BADGIFY_CONFIG`.
+/// Every section is optional and defaults to the behaviour of a bare server.
+#[derive(Debug, Default, Deserialize)]
+#[serde(default)]
+pub struct Config {
+    pub indexer: IndexerConfig,
+    pub rpc: RpcConfig,
+    pub chain_list: ChainListConfig,
+    pub admin: AdminConfig,
+    pub cache: CacheConfig,
+    pub history: HistoryConfig,
+}
+
+impl Config {
+    pub fn load() -> Result<Self, Box<dyn Error>> {
+        match std::env::var(CONFIG_PATH_ENV) {
+            Ok(path) => Self::from_file(&path),
+            Err(_) => Ok(Self::default()),
+        }
+    }
+
+    pub fn from_file(path: &str) -> Result<Self, Box<dyn Error>> {
+        let contents = std::fs::read_to_string(path)
+            .map_err(|err| format!("Cannot read config file {path}: {err}"))?;
+
+        contents.parse()
+    }
+}
+
+impl FromStr for Config {
+    type Err = Box<dyn Error>;
+
+    fn from_str(contents: &str) -> Result<Self, Self::Err> {
+        Ok(toml::from_str(contents)?)
+    }
+}
+
+#[derive(Debug, Clone, Deserialize)]
+#[serde(default)]
+pub struct IndexerConfig {
+    pub enabled: bool,
+    pub database_path: PathBuf,
+    pub poll_interval_secs: u64,
+    /// How many recent blocks are rolled back and re-scanned on every poll.
+    pub reorg_depth: u64,
+    pub tokens: Vec<IndexedToken>,
+}
+
+impl Default for IndexerConfig {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            database_path: PathBuf::from("badgify_index.sqlite"),
+            poll_interval_secs: 15,
+            reorg_depth: 12,
+            tokens: Vec::new(),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Deserialize)]
+pub struct IndexedToken {
+    pub chain_id: u64,
+    pub address: EvmAddress,
+    /// The token's deployment block; holders are only correct when indexing
+    /// starts at or before it.
+    pub start_block: u64,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+#[serde(default)]
+pub struct RpcConfig {
+    /// How often the endpoints of recently used chains are probed.
+    pub probe_interval_secs: u64,
+    /// Consecutive failures after which an endpoint is ejected.
+    pub failure_threshold: u32,
+    /// How long an ejected endpoint is left out of rotation.
+    pub cooldown_secs: u64,
+    /// Endpoints further behind the best known block are used last.
+    pub max_block_lag: u64,
+    /// How many extra endpoints may be raced against a slow one; 0 tries
+    /// endpoints one after another.
+    pub max_hedges: usize,
+    /// How long an endpoint may take before the next one is raced against it.
+    pub hedge_delay_ms: u64,
+    /// Deadline for a request across all endpoints tried.
+    pub request_timeout_secs: Option<u64>,
+    /// Cross-checks state reads across several endpoints when set.
+    pub quorum: Option<QuorumConfig>,
+    /// Operator endpoints, tried before the public ones from chainlist.
+    pub endpoints: Vec<ChainEndpoints>,
+    /// Directory searched for `${NAME}` placeholders that are not set in the
+    /// environment, one file per secret.
+    pub secrets_dir: PathBuf,
+}
+
+impl Default for RpcConfig {
+    fn default() -> Self {
+        Self {
+            probe_interval_secs: 30,
+            failure_threshold: 3,
+            cooldown_secs: 60,
+            max_block_lag: 5,
+            max_hedges: 0,
+            hedge_delay_ms: 1_000,
+            request_timeout_secs: None,
+            quorum: None,
+            endpoints: Vec::new(),
+            secrets_dir: PathBuf::from("/run/secrets"),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Deserialize)]
+pub struct ChainEndpoints {
+    pub chain_id: u64,
+    /// RPC URLs which may contain `${NAME}` placeholders for API keys.
+    pub urls: Vec<String>,
+    /// Whether the public endpoints from chainlist are used as a fallback.
+    #[serde(default = "default_use_chainlist")]
+    pub use_chainlist: bool,
+    /// Keeps a WebSocket connection open to the `ws://` or `wss://` URLs of
+    /// the chain (or those from chainlist) and follows its new blocks.
+    #[serde(default)]
+    pub websocket: bool,
+}
+
+fn default_use_chainlist() -> bool {
+    true
+}
+
+#[derive(Debug, Clone, Deserialize)]
+pub struct QuorumConfig {
+    /// How many endpoints are queried.
+    pub endpoints: usize,
+    /// How many of them must return the same value.
+    pub required: usize,
+    /// How far behind the latest block reads are pinned, so that endpoints
+    /// slightly behind can still take part.
+    #[serde(default = "default_quorum_confirmations")]
+    pub confirmations: u64,
+}
+
+fn default_quorum_confirmations() -> u64 {
+    2
+}
+
+#[derive(Debug, Clone, Deserialize)]
+#[serde(default)]
+pub struct ChainListConfig {
+    /// URL or local path of a `chains.json` in the chainid.network format,
+    /// instead of chainid.network itself.
+    pub source: Option<String>,
+    pub refresh_interval_secs: u64,
+    /// URL or local directory of the chainlist icon definitions
+    /// (`<icon>.json`), instead of the ethereum-lists repository.
+    pub icons_source: Option<String>,
+    /// Gateway used to download icons referenced by `ipfs://` URLs.
+    pub ipfs_gateway: String,
+}
+
+impl Default for ChainListConfig {
+    fn default() -> Self {
+        Self {
+            source: None,
+            refresh_interval_secs: 24 * 60 * 60,
+            icons_source: None,
+            ipfs_gateway: "https://ipfs.io/ipfs".to_string(),
+        }
+    }
+}
+
+#[derive(Debug, Default, Deserialize)]
+#[serde(default)]
+pub struct AdminConfig {
+    /// Bearer token for the `/api/admin` endpoints, which are disabled without one.
+    pub token: Option<String>,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+#[serde(default)]
+pub struct CacheConfig {
+    pub max_entries: usize,
+    /// Overrides the time results are cached for, in seconds, by query type
+    /// (`balance`, `erc20_flow`, `btc_balance`, ...). `0` disables caching.
+    pub ttl_secs: HashMap<String, u64>,
+    /// Longest time the last successful result of a query is served for while
+    /// upstreams fail. `0` disables serving stale results.
+    pub stale_max_age_secs: u64,
+    /// SQLite database keeping the last results across restarts.
+    pub stale_database_path: Option<PathBuf>,
+}
+
+impl Default for CacheConfig {
+    fn default() -> Self {
+        Self {
+            max_entries: 10_000,
+            ttl_secs: HashMap::new(),
+            stale_max_age_secs: 24 * 60 * 60,
+            stale_database_path: None,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Deserialize)]
+#[serde(default)]
+pub struct HistoryConfig {
+    pub enabled: bool,
+    pub database_path: PathBuf,
+    /// Time between two recorded results of every query, in seconds.
+    pub interval_secs: u64,
+    /// Query paths to record, e.g. `evm/1/balance/0x...`.
+    pub queries: Vec<String>,
+}
+
+impl Default for HistoryConfig {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            database_path: PathBuf::from("badgify_history.sqlite"),
+            interval_secs: 60 * 60,
+            queries: Vec::new(),
+        }
+    }
+}
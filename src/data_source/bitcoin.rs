@@ -1,11 +1,14 @@
 use alloy::primitives::U256;
 use serde::{Deserialize, Serialize};
-use std::error::Error;
 
-use crate::query::bitcoin::{BitcoinNetwork, BitcoinQuery};
+use crate::{
+    error::BadgifyError,
+    query::bitcoin::{BitcoinNetwork, BitcoinQuery},
+};
 
 use super::{
-    bitcoing_metadata::BitcoinMetadata, SourceMetadata, SourceResponse, SourceResponseWithMetadata,
+    bitcoing_metadata::{BitcoinMetadata, BitcoinSource},
+    SourceMetadata, SourceResponse, SourceResponseWithMetadata,
 };
 
 #[derive(Default)]
@@ -15,17 +18,19 @@ impl BitcoinDataSource {
     pub async fn get_data(
         &self,
         query: BitcoinQuery,
-    ) -> Result<SourceResponseWithMetadata, Box<dyn Error>> {
+    ) -> Result<SourceResponseWithMetadata, BadgifyError> {
         match query {
             BitcoinQuery::NativeBalance { network, address } => {
                 self.get_native_balance(network, address).await
             }
+            BitcoinQuery::TxCount { network, address } => self.get_tx_count(network, address).await,
         }
     }
 
-    pub async fn get_scanner_link(&self, query: BitcoinQuery) -> Result<String, Box<dyn Error>> {
+    pub async fn get_scanner_link(&self, query: BitcoinQuery) -> Result<String, BadgifyError> {
         match query {
-            BitcoinQuery::NativeBalance { network, address } => {
+            BitcoinQuery::NativeBalance { network, address }
+            | BitcoinQuery::TxCount { network, address } => {
                 Ok(get_scanner_link(&network, &address))
             }
         }
@@ -35,7 +40,7 @@ impl BitcoinDataSource {
         &self,
         network: BitcoinNetwork,
         address: String,
-    ) -> Result<SourceResponseWithMetadata, Box<dyn Error>> {
+    ) -> Result<SourceResponseWithMetadata, BadgifyError> {
         let address_info = get_address_info(&network, &address).await?;
 
         let result = SourceResponse::Decimal {
@@ -45,7 +50,25 @@ impl BitcoinDataSource {
             decimals: 8u8,
         };
 
-        let metadata = SourceMetadata::Bitcoin(BitcoinMetadata::new(network));
+        let metadata =
+            SourceMetadata::Bitcoin(BitcoinMetadata::new(network, BitcoinSource::NativeBalance));
+
+        Ok(SourceResponseWithMetadata::new(result, metadata))
+    }
+
+    async fn get_tx_count(
+        &self,
+        network: BitcoinNetwork,
+        address: String,
+    ) -> Result<SourceResponseWithMetadata, BadgifyError> {
+        let address_info = get_address_info(&network, &address).await?;
+
+        let result = SourceResponse::Integer {
+            value: U256::from(address_info.chain_stats.tx_count),
+        };
+
+        let metadata =
+            SourceMetadata::Bitcoin(BitcoinMetadata::new(network, BitcoinSource::TxCount));
 
         Ok(SourceResponseWithMetadata::new(result, metadata))
     }
@@ -79,7 +102,7 @@ struct MempoolIoMempoolStats {
 async fn get_address_info(
     network: &BitcoinNetwork,
     address: &str,
-) -> Result<MempoolIoResponse, Box<dyn Error>> {
+) -> Result<MempoolIoResponse, BadgifyError> {
     let mut req_url = "https://mempool.space".to_string();
 
     match network {
@@ -1,26 +1,44 @@
-use serde::Serialize;
+use serde::{Deserialize, Serialize};
 
 use crate::query::bitcoin::BitcoinNetwork;
 
-#[derive(Serialize)]
+#[derive(Serialize, Deserialize)]
 pub struct BitcoinMetadata {
     pub network: BitcoinNetwork,
+    pub source: BitcoinSource,
 }
 
 impl BitcoinMetadata {
-    pub fn new(network: BitcoinNetwork) -> Self {
-        Self { network }
+    pub fn new(network: BitcoinNetwork, source: BitcoinSource) -> Self {
+        Self { network, source }
     }
 
-    pub fn symbol(&self) -> String {
-        "BTC".to_string()
+    pub fn symbol(&self) -> Option<String> {
+        match self.source {
+            BitcoinSource::NativeBalance => Some("BTC".to_string()),
+            BitcoinSource::TxCount => None,
+        }
     }
 
     pub fn label(&self) -> Option<String> {
-        "Bitcoin".to_string().into()
+        match self.source {
+            BitcoinSource::NativeBalance => "Bitcoin".to_string().into(),
+            BitcoinSource::TxCount => "Bitcoin transactions".to_string().into(),
+        }
     }
 
     pub fn logo(&self) -> Option<String> {
         "bitcoin".to_string().into()
     }
+
+    pub fn is_testnet(&self) -> bool {
+        !matches!(self.network, BitcoinNetwork::Mainnet)
+    }
+}
+
+#[derive(Serialize, Deserialize)]
+#[serde(tag = "type")]
+pub enum BitcoinSource {
+    NativeBalance,
+    TxCount,
 }
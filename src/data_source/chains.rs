@@ -0,0 +1,74 @@
+use std::sync::Arc;
+
+use serde::Serialize;
+
+use crate::{evm_chainlist::EvmChain, query::bitcoin::BitcoinNetwork};
+
+/// A network Badgify can query, as listed by `/api/chains`.
+#[derive(Serialize)]
+pub struct ChainInfo {
+    /// Chain ID of EVM chains, `btc-<network>` for Bitcoin networks.
+    pub id: String,
+    pub name: String,
+    pub short_name: String,
+    /// Start of the queries that read from this chain, e.g. `evm/1`.
+    pub query_prefix: String,
+    pub testnet: bool,
+    pub deprecated: bool,
+    /// Whether an RPC endpoint is known that data can be read from.
+    pub rpc_available: bool,
+    /// Whether scanner links can be resolved to an EIP-3091 explorer.
+    pub explorer_available: bool,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub chain: Option<Arc<EvmChain>>,
+}
+
+impl ChainInfo {
+    pub fn evm(chain: Arc<EvmChain>, rpc_available: bool) -> Self {
+        Self {
+            id: chain.chain_id.to_string(),
+            name: chain.name.clone(),
+            short_name: chain.short_name.clone(),
+            query_prefix: format!("evm/{}", chain.chain_id),
+            testnet: chain.is_testnet(),
+            deprecated: chain.is_deprecated(),
+            rpc_available,
+            explorer_available: chain
+                .explorers
+                .iter()
+                .flatten()
+                .any(|explorer| explorer.standard == "EIP3091"),
+            chain: Some(chain),
+        }
+    }
+
+    pub fn bitcoin(network: BitcoinNetwork) -> Self {
+        let (name, network_name) = match network {
+            BitcoinNetwork::Mainnet => ("Bitcoin", "mainnet"),
+            BitcoinNetwork::Testnet => ("Bitcoin Testnet", "testnet"),
+            BitcoinNetwork::Signet => ("Bitcoin Signet", "signet"),
+        };
+
+        Self {
+            id: format!("btc-{network_name}"),
+            name: name.to_string(),
+            short_name: format!("btc-{network_name}"),
+            query_prefix: format!("btc/{network_name}"),
+            testnet: !matches!(network, BitcoinNetwork::Mainnet),
+            deprecated: false,
+            // Bitcoin data and scanner links both come from mempool.space.
+            rpc_available: true,
+            explorer_available: true,
+            chain: None,
+        }
+    }
+
+    /// Returns whether `query` is part of the name, short name or ID.
+    pub fn matches(&self, query: &str) -> bool {
+        let query = query.to_lowercase();
+
+        [&self.name, &self.short_name, &self.id]
+            .into_iter()
+            .any(|field| field.to_lowercase().contains(&query))
+    }
+}
@@ -1,31 +1,59 @@
 use rand::seq::SliceRandom;
-use std::{collections::HashMap, error::Error, future::Future, sync::Arc};
-use tokio::sync::RwLock;
+use std::{
+    collections::{HashMap, HashSet},
+    error::Error,
+    future::Future,
+    sync::{Arc, Mutex},
+    time::{Duration, Instant},
+};
+use tokio::task::JoinSet;
 
 use alloy::{
-    primitives::{Address, Bytes},
+    primitives::{b256, keccak256, Address, B256, I256, U256, U64},
     providers::{Provider, ProviderBuilder},
     rpc::{
-        client::{ClientBuilder, ReqwestClient, Waiter},
-        types::{TransactionInput, TransactionRequest},
+        client::{ClientBuilder, ReqwestClient},
+        types::{
+            BlockId, BlockNumberOrTag, BlockTransactionsKind, TransactionInput, TransactionRequest,
+        },
     },
     sol,
     sol_types::SolCall,
 };
 
 use crate::{
+    config::{Config, RpcConfig},
+    error::BadgifyError,
     evm_chainlist::{EvmChain, EvmChainList},
-    query::EvmQuery,
+    query::{BlockWindow, ChainMetric, EvmQuery, FlowMetric, ProxySlot, SafeField, StorageKind},
     types::ChainID,
+    utils::unix_now,
 };
 
 use super::{
-    evm_metadata::{EvmMetadata, EvmSource},
+    chains::ChainInfo,
+    evm_hedging::{sleep_until_deadline, Hedging, InFlight},
+    evm_indexer::HolderIndex,
+    evm_logs,
+    evm_metadata::{CodeKind, EvmMetadata, EvmSource},
+    evm_multicall::{self, ContractCall, Multicall},
+    evm_quorum::{self, Verifiable},
+    evm_ws::WsConnections,
+    rpc_endpoints::RpcEndpoints,
+    rpc_health::{EndpointReport, RpcHealth},
     SourceMetadata, SourceResponse, SourceResponseWithMetadata,
 };
 
 const ETH_DECIMALS: u8 = 18;
-const QUERY_BLOCK: &str = "latest";
+const GWEI_DECIMALS: u8 = 9;
+
+// bytes32(uint256(keccak256("eip1967.proxy.implementation")) - 1)
+const EIP1967_IMPLEMENTATION_SLOT: B256 =
+    b256!("360894a13ba1a3210667c828492db98dca3e2076cc3735a920a3ca505d382bbc");
+// bytes32(uint256(keccak256("eip1967.proxy.admin")) - 1)
+const EIP1967_ADMIN_SLOT: B256 =
+    b256!("b53127684a568b3173ae13b9f8a6016e243e63b6e8ee1178d6a717850b5d6103");
+const PROBE_TIMEOUT: Duration = Duration::from_secs(5);
 
 sol! {
     #[sol(rpc)]
@@ -38,25 +66,186 @@ sol! {
 
         #[derive(Debug)]
         function symbol() public view returns (string symbol);
+
+        #[derive(Debug)]
+        event Transfer(address indexed from, address indexed to, uint256 value);
+    }
+}
+
+sol! {
+    #[sol(rpc)]
+    contract Safe {
+        #[derive(Debug)]
+        function getThreshold() public view returns (uint256 threshold);
+
+        #[derive(Debug)]
+        function getOwners() public view returns (address[] owners);
+
+        #[derive(Debug)]
+        function nonce() public view returns (uint256 nonce);
+    }
+}
+
+sol! {
+    #[sol(rpc)]
+    contract Ownable {
+        #[derive(Debug)]
+        function owner() public view returns (address owner);
+    }
+}
+
+sol! {
+    #[sol(rpc)]
+    contract EnsRegistry {
+        #[derive(Debug)]
+        function owner(bytes32 node) external view returns (address owner);
+    }
+}
+
+sol! {
+    #[sol(rpc)]
+    contract BaseRegistrar {
+        #[derive(Debug)]
+        function nameExpires(uint256 id) external view returns (uint256 expires);
     }
 }
 
 pub struct EvmDataSource {
-    last_known_good_rpc_urls: Arc<RwLock<HashMap<ChainID, String>>>,
-    chain_list: EvmChainList,
+    rpc_health: Arc<RpcHealth>,
+    rpc_endpoints: RpcEndpoints,
+    rpc_config: RpcConfig,
+    websockets: Arc<WsConnections>,
+    chain_list: Arc<EvmChainList>,
+    holder_index: Option<Arc<HolderIndex>>,
+    multicall: Multicall,
+    /// Deprecated chains that were already warned about.
+    deprecated_warned: Mutex<HashSet<ChainID>>,
 }
 
-impl Default for EvmDataSource {
-    fn default() -> Self {
-        Self {
-            last_known_good_rpc_urls: Arc::new(HashMap::new().into()),
-            chain_list: EvmChainList::default(),
+impl EvmDataSource {
+    pub fn new(config: &Config) -> Result<Self, Box<dyn Error>> {
+        if let Some(quorum) = &config.rpc.quorum {
+            if quorum.required == 0 || quorum.required > quorum.endpoints {
+                return Err(
+                    "rpc.quorum.required must be between 1 and rpc.quorum.endpoints".into(),
+                );
+            }
+        }
+
+        let holder_index = if config.indexer.enabled {
+            Some(Arc::new(HolderIndex::open(config.indexer.clone())?))
+        } else {
+            None
+        };
+
+        Ok(Self {
+            rpc_health: Arc::new(RpcHealth::new(&config.rpc)),
+            rpc_endpoints: RpcEndpoints::from_config(&config.rpc)?,
+            rpc_config: config.rpc.clone(),
+            websockets: Arc::default(),
+            chain_list: Arc::new(EvmChainList::new(&config.chain_list)?),
+            holder_index,
+            multicall: Multicall::default(),
+            deprecated_warned: Mutex::default(),
+        })
+    }
+
+    pub fn holder_index(&self) -> Option<Arc<HolderIndex>> {
+        self.holder_index.clone()
+    }
+
+    pub fn rpc_health_report(&self) -> HashMap<String, Vec<EndpointReport>> {
+        let mut report = self.rpc_health.report();
+
+        for endpoint in report.values_mut().flatten() {
+            endpoint.url = self.rpc_endpoints.redact(&endpoint.url);
+        }
+
+        report
+    }
+
+    pub fn chain_list(&self) -> Arc<EvmChainList> {
+        self.chain_list.clone()
+    }
+
+    pub fn chain_info(&self, chain: Arc<EvmChain>) -> ChainInfo {
+        let (operator_urls, public_urls) = self.rpc_urls(&chain);
+        let rpc_available = !operator_urls.is_empty()
+            || !public_urls.is_empty()
+            || !self.websocket_urls(&chain).is_empty();
+
+        ChainInfo::evm(chain, rpc_available)
+    }
+
+    pub fn websocket_chains(&self) -> Vec<ChainID> {
+        self.rpc_endpoints.websocket_chains()
+    }
+
+    /// Returns the latest block announced over WebSocket for `chain_id`.
+    pub fn chain_head(&self, chain_id: ChainID) -> Option<u64> {
+        self.websockets.head(chain_id)
+    }
+
+    /// Follows the new heads of `chain_id` over WebSocket forever.
+    pub async fn run_websocket(self: Arc<Self>, chain_id: ChainID) {
+        // Chains missing from the bundled snapshot appear after a refresh.
+        let chain = loop {
+            match self.chain_list.get_chain(chain_id) {
+                Some(chain) => break chain,
+                None => {
+                    println!("Cannot follow unknown chain {chain_id} over WebSocket yet");
+                    tokio::time::sleep(Duration::from_secs(self.rpc_config.probe_interval_secs))
+                        .await;
+                }
+            }
+        };
+
+        let urls = self.websocket_urls(&chain);
+        if urls.is_empty() {
+            println!("No WebSocket RPC URLs for chain {chain_id}");
+            return;
+        }
+
+        self.websockets
+            .clone()
+            .run(chain_id, urls, self.rpc_health.clone(), |text| {
+                self.rpc_endpoints.redact(text)
+            })
+            .await;
+    }
+
+    /// Measures the latency and block height of every endpoint of the chains
+    /// in use, forever. Ejected endpoints are probed again once their
+    /// cool-down is over.
+    pub async fn run_health_probes(self: Arc<Self>) {
+        let probe_interval = Duration::from_secs(self.rpc_config.probe_interval_secs);
+
+        loop {
+            tokio::time::sleep(probe_interval).await;
+
+            for chain_id in self.rpc_health.tracked_chains() {
+                let Some(chain) = self.chain_list.get_chain(chain_id) else {
+                    continue;
+                };
+
+                let mut probes = JoinSet::new();
+
+                let (operator_urls, public_urls) = self.rpc_urls(&chain);
+
+                for rpc_url in operator_urls.into_iter().chain(public_urls) {
+                    if self.rpc_health.is_available(chain_id, &rpc_url) {
+                        probes.spawn(probe_rpc_url(self.rpc_health.clone(), chain_id, rpc_url));
+                    }
+                }
+
+                probes.join_all().await;
+            }
         }
     }
 }
 
 impl EvmDataSource {
-    pub async fn get_scanner_link(&self, evm_query: EvmQuery) -> Result<String, Box<dyn Error>> {
+    pub async fn get_scanner_link(&self, evm_query: EvmQuery) -> Result<String, BadgifyError> {
         match evm_query {
             EvmQuery::NativeBalance { chain_id, address } => {
                 self.get_address_page_link(chain_id, address).await
@@ -69,6 +258,65 @@ impl EvmDataSource {
                 self.get_erc20_balance_page_link(chain_id, contract_address, address)
                     .await
             }
+            EvmQuery::Safe {
+                chain_id,
+                safe_address,
+                field: _,
+            } => self.get_address_page_link(chain_id, safe_address).await,
+            EvmQuery::EnsExpiry { chain_id: _, name } => {
+                Ok(format!("https://app.ens.domains/{name}"))
+            }
+            EvmQuery::Proxy {
+                chain_id,
+                proxy_address,
+                slot,
+            } => {
+                let target = self.read_proxy_slot(chain_id, proxy_address, slot).await?;
+                self.get_address_page_link(chain_id, target).await
+            }
+            EvmQuery::Owner {
+                chain_id,
+                contract_address,
+            } => {
+                let owner = self.read_owner(chain_id, contract_address).await?;
+                self.get_address_page_link(chain_id, owner).await
+            }
+            EvmQuery::Storage {
+                chain_id,
+                contract_address,
+                ..
+            } => self.get_address_page_link(chain_id, contract_address).await,
+            EvmQuery::CodeSize { chain_id, address }
+            | EvmQuery::IsContract { chain_id, address } => {
+                self.get_address_page_link(chain_id, address).await
+            }
+            EvmQuery::ChainStatus {
+                chain_id,
+                metric: _,
+            } => self.eip3091_explorer_url(chain_id).await,
+            EvmQuery::TxCount {
+                chain_id,
+                address,
+                pending: _,
+            } => self.get_address_page_link(chain_id, address).await,
+            EvmQuery::ERC20Flow {
+                chain_id,
+                address,
+                contract_address,
+                ..
+            } => {
+                self.get_erc20_balance_page_link(chain_id, contract_address, address)
+                    .await
+            }
+            EvmQuery::ERC20Holders {
+                chain_id,
+                contract_address,
+            } => {
+                let mut link = self.eip3091_explorer_url(chain_id).await?;
+                link.push_str("/token/");
+                link.push_str(&contract_address.to_string());
+                Ok(link)
+            }
         }
     }
 
@@ -76,7 +324,7 @@ impl EvmDataSource {
         &self,
         chain_id: ChainID,
         address: Address,
-    ) -> Result<String, Box<dyn Error>> {
+    ) -> Result<String, BadgifyError> {
         let mut link = self.eip3091_explorer_url(chain_id).await?;
 
         link.push_str("/address/");
@@ -90,7 +338,7 @@ impl EvmDataSource {
         chain_id: ChainID,
         contract_address: Address,
         address: Address,
-    ) -> Result<String, Box<dyn Error>> {
+    ) -> Result<String, BadgifyError> {
         let mut link = self.eip3091_explorer_url(chain_id).await?;
 
         link.push_str("/token/");
@@ -101,21 +349,24 @@ impl EvmDataSource {
         Ok(link)
     }
 
-    async fn eip3091_explorer_url(&self, chain_id: ChainID) -> Result<String, Box<dyn Error>> {
+    async fn eip3091_explorer_url(&self, chain_id: ChainID) -> Result<String, BadgifyError> {
         let chain = self
             .chain_list
-            .fetch_evm_chain(chain_id)
-            .await?
-            .ok_or("Chain Not Found")?;
+            .get_chain(chain_id)
+            .ok_or(BadgifyError::UnknownChain(chain_id))?;
 
-        let explorers = chain
-            .explorers
-            .ok_or(format!("No explorer found for chain {chain_id}"))?;
+        let explorers = chain.explorers.as_ref().ok_or_else(|| {
+            BadgifyError::Unavailable(format!("No explorer found for chain {chain_id}"))
+        })?;
 
         let explorer = explorers
             .iter()
             .find(|ex| ex.standard == "EIP3091")
-            .ok_or(format!("No EIP3091 explorers found for chain {chain_id}"))?;
+            .ok_or_else(|| {
+                BadgifyError::Unavailable(format!(
+                    "No EIP3091 explorers found for chain {chain_id}"
+                ))
+            })?;
 
         Ok(explorer.url.clone())
     }
@@ -125,8 +376,8 @@ impl EvmDataSource {
     pub async fn get_data(
         &self,
         evm_query: EvmQuery,
-    ) -> Result<SourceResponseWithMetadata, Box<dyn Error>> {
-        match evm_query {
+    ) -> Result<SourceResponseWithMetadata, BadgifyError> {
+        let result = match evm_query {
             EvmQuery::NativeBalance { chain_id, address } => {
                 self.get_native_balance(chain_id, address).await
             }
@@ -138,16 +389,85 @@ impl EvmDataSource {
                 self.get_erc20_balance(chain_id, contract_address, address)
                     .await
             }
+            EvmQuery::Safe {
+                chain_id,
+                safe_address,
+                field,
+            } => self.get_safe_info(chain_id, safe_address, field).await,
+            EvmQuery::EnsExpiry { chain_id, name } => self.get_ens_expiry(chain_id, name).await,
+            EvmQuery::Proxy {
+                chain_id,
+                proxy_address,
+                slot,
+            } => self.get_proxy_target(chain_id, proxy_address, slot).await,
+            EvmQuery::Owner {
+                chain_id,
+                contract_address,
+            } => self.get_contract_owner(chain_id, contract_address).await,
+            EvmQuery::Storage {
+                chain_id,
+                contract_address,
+                slot,
+                kind,
+            } => {
+                self.get_storage(chain_id, contract_address, slot, kind)
+                    .await
+            }
+            EvmQuery::CodeSize { chain_id, address } => {
+                self.get_code(chain_id, address, CodeKind::Size).await
+            }
+            EvmQuery::IsContract { chain_id, address } => {
+                self.get_code(chain_id, address, CodeKind::IsContract).await
+            }
+            EvmQuery::ChainStatus { chain_id, metric } => {
+                self.get_chain_status(chain_id, metric).await
+            }
+            EvmQuery::TxCount {
+                chain_id,
+                address,
+                pending,
+            } => self.get_tx_count(chain_id, address, pending).await,
+            EvmQuery::ERC20Flow {
+                chain_id,
+                address,
+                contract_address,
+                metric,
+                window,
+            } => {
+                self.get_erc20_flow(chain_id, contract_address, address, metric, window)
+                    .await
+            }
+            EvmQuery::ERC20Holders {
+                chain_id,
+                contract_address,
+            } => self.get_erc20_holders(chain_id, contract_address).await,
+        }?;
+
+        if let SourceMetadata::Evm(metadata) = &result.metadata {
+            if metadata.deprecated
+                && self
+                    .deprecated_warned
+                    .lock()
+                    .unwrap()
+                    .insert(metadata.chain.chain_id)
+            {
+                println!(
+                    "Warning: serving data from deprecated chain {} ({})",
+                    metadata.chain.name, metadata.chain.chain_id
+                );
+            }
         }
+
+        Ok(result)
     }
 
     async fn get_native_balance(
         &self,
         chain_id: ChainID,
         address: Address,
-    ) -> Result<SourceResponseWithMetadata, Box<dyn Error>> {
-        self.try_with_rpc_urls_provider(chain_id, move |chain, provider| async move {
-            match provider.get_balance(address).await {
+    ) -> Result<SourceResponseWithMetadata, BadgifyError> {
+        self.read_state_with_provider(chain_id, move |chain, provider, block| async move {
+            match provider.get_balance(address).block_id(block).await {
                 Ok(res) => {
                     let result = SourceResponse::Decimal {
                         value: res,
@@ -163,7 +483,7 @@ impl EvmDataSource {
 
                     Ok(SourceResponseWithMetadata::new(result, metadata))
                 }
-                Err(err) => Err(Box::new(err) as Box<dyn Error>),
+                Err(err) => Err(err.into()),
             }
         })
         .await
@@ -174,186 +494,863 @@ impl EvmDataSource {
         chain_id: ChainID,
         contract_address: Address,
         address: Address,
-    ) -> Result<SourceResponseWithMetadata, Box<dyn Error>> {
-        self.try_with_rpc_urls_client(chain_id, move |chain, client| async move {
-            let mut batch = client.new_batch();
-
-            let balance_call = ERC20::balanceOfCall::new((address,));
-            let decimals_call = ERC20::decimalsCall::new(());
-            let symbol_call = ERC20::symbolCall::new(());
-
-            let balance_fut: Waiter<Bytes> = batch
-                .add_call(
-                    "eth_call",
-                    &(
-                        TransactionRequest::default()
-                            .to(contract_address)
-                            .input(TransactionInput::from(balance_call.abi_encode())),
-                        QUERY_BLOCK,
-                    ),
-                )
-                .unwrap();
-
-            let decimals_fut: Waiter<Bytes> = batch
-                .add_call(
-                    "eth_call",
-                    &(
-                        TransactionRequest::default()
-                            .to(contract_address)
-                            .input(TransactionInput::from(decimals_call.abi_encode())),
-                        QUERY_BLOCK,
-                    ),
-                )
-                .unwrap();
-
-            let symbol_fut: Waiter<Bytes> = batch
-                .add_call(
-                    "eth_call",
-                    &(
-                        TransactionRequest::default()
-                            .to(contract_address)
-                            .input(TransactionInput::from(symbol_call.abi_encode())),
-                        QUERY_BLOCK,
-                    ),
-                )
-                .unwrap();
-
-            if batch.send().await.is_ok() {
-                match (balance_fut.await, decimals_fut.await, symbol_fut.await) {
-                    (Ok(balance), Ok(decimals), Ok(symbol)) => {
-                        let balance =
-                            ERC20::balanceOfCall::abi_decode_returns(&balance, true)?.balance;
-                        let decimals =
-                            ERC20::decimalsCall::abi_decode_returns(&decimals, true)?.decimals;
-                        let symbol = ERC20::symbolCall::abi_decode_returns(&symbol, true)?.symbol;
-
-                        let result = SourceResponse::Decimal {
-                            value: balance,
-                            decimals,
-                        };
-
-                        let metadata = SourceMetadata::Evm(EvmMetadata::new(
-                            chain,
-                            EvmSource::ERC20 {
-                                symbol,
-                                contract_address,
-                            },
-                        ));
-
-                        Ok(SourceResponseWithMetadata::new(result, metadata))
+    ) -> Result<SourceResponseWithMetadata, BadgifyError> {
+        let multicall = self.multicall.clone();
+
+        self.read_state_with_client(chain_id, move |chain, client, block| {
+            let multicall = multicall.clone();
+            async move {
+                let calls = [
+                    ContractCall::new(contract_address, &ERC20::balanceOfCall::new((address,))),
+                    ContractCall::new(contract_address, &ERC20::decimalsCall::new(())),
+                    ContractCall::new(contract_address, &ERC20::symbolCall::new(())),
+                ];
+
+                let results = multicall.call(chain_id, &client, &calls, block).await?;
+
+                let balance = evm_multicall::decode::<ERC20::balanceOfCall>(&results[0])?.balance;
+                let decimals = evm_multicall::decode::<ERC20::decimalsCall>(&results[1])?.decimals;
+                // Some tokens have no (or a non-string) symbol; the balance is still useful.
+                let symbol = evm_multicall::decode::<ERC20::symbolCall>(&results[2])
+                    .map(|symbol| symbol.symbol)
+                    .unwrap_or_default();
+
+                let result = SourceResponse::Decimal {
+                    value: balance,
+                    decimals,
+                };
+
+                let metadata = SourceMetadata::Evm(EvmMetadata::new(
+                    chain,
+                    EvmSource::ERC20 {
+                        symbol,
+                        contract_address,
+                    },
+                ));
+
+                Ok(SourceResponseWithMetadata::new(result, metadata))
+            }
+        })
+        .await
+    }
+
+    async fn get_safe_info(
+        &self,
+        chain_id: ChainID,
+        safe_address: Address,
+        field: SafeField,
+    ) -> Result<SourceResponseWithMetadata, BadgifyError> {
+        let multicall = self.multicall.clone();
+
+        self.read_state_with_client(chain_id, move |chain, client, block| {
+            let multicall = multicall.clone();
+            async move {
+                let calls = [
+                    ContractCall::new(safe_address, &Safe::getThresholdCall::new(())),
+                    ContractCall::new(safe_address, &Safe::getOwnersCall::new(())),
+                    ContractCall::new(safe_address, &Safe::nonceCall::new(())),
+                ];
+
+                let results = multicall.call(chain_id, &client, &calls, block).await?;
+
+                let threshold =
+                    evm_multicall::decode::<Safe::getThresholdCall>(&results[0])?.threshold;
+                let owners = evm_multicall::decode::<Safe::getOwnersCall>(&results[1])?.owners;
+                let nonce = evm_multicall::decode::<Safe::nonceCall>(&results[2])?.nonce;
+
+                let threshold: u64 = threshold.try_into()?;
+
+                let result = match field {
+                    SafeField::Threshold => SourceResponse::AlphaNumeric {
+                        value: format!("{} of {}", threshold, owners.len()),
+                    },
+                    SafeField::Owners => SourceResponse::Integer {
+                        value: U256::from(owners.len()),
+                    },
+                    SafeField::Nonce => SourceResponse::Integer { value: nonce },
+                };
+
+                let metadata = SourceMetadata::Evm(EvmMetadata::new(
+                    chain,
+                    EvmSource::Safe {
+                        safe_address,
+                        field,
+                        threshold,
+                        owners,
+                        nonce,
+                    },
+                ));
+
+                Ok(SourceResponseWithMetadata::new(result, metadata))
+            }
+        })
+        .await
+    }
+
+    async fn get_ens_expiry(
+        &self,
+        chain_id: ChainID,
+        name: String,
+    ) -> Result<SourceResponseWithMetadata, BadgifyError> {
+        let label = name.trim_end_matches(".eth");
+        let token_id = U256::from_be_bytes(keccak256(label.as_bytes()).0);
+        // namehash("eth")
+        let eth_node = keccak256([B256::ZERO, keccak256("eth")].concat());
+
+        self.read_state_with_provider(chain_id, move |chain, provider, block| {
+            let name = name.clone();
+            async move {
+                let registry = chain
+                    .ens
+                    .as_ref()
+                    .ok_or_else(|| {
+                        BadgifyError::Unavailable(format!("ENS is not deployed on {}", chain.name))
+                    })?
+                    .registry;
+
+                // The .eth registrar is the owner of the eth node in the registry.
+                let call = EnsRegistry::ownerCall::new((eth_node,));
+                let tx = TransactionRequest::default()
+                    .to(registry)
+                    .input(TransactionInput::from(call.abi_encode()));
+
+                let registrar = provider.call(&tx).block(block).await?;
+                let registrar = EnsRegistry::ownerCall::abi_decode_returns(&registrar, true)?.owner;
+
+                if registrar.is_zero() {
+                    return Err(BadgifyError::Unavailable(format!(
+                        "No .eth registrar on {}",
+                        chain.name
+                    )));
+                }
+
+                let call = BaseRegistrar::nameExpiresCall::new((token_id,));
+                let tx = TransactionRequest::default()
+                    .to(registrar)
+                    .input(TransactionInput::from(call.abi_encode()));
+
+                let expires = provider.call(&tx).block(block).await?;
+                let expires =
+                    BaseRegistrar::nameExpiresCall::abi_decode_returns(&expires, true)?.expires;
+
+                if expires.is_zero() {
+                    return Err(BadgifyError::NotFound(format!(
+                        "ENS name {name} is not registered"
+                    )));
+                }
+
+                let expires_at: u64 = expires.try_into()?;
+
+                let result = SourceResponse::Timestamp { value: expires_at };
+                let metadata = SourceMetadata::Evm(EvmMetadata::new(
+                    chain,
+                    EvmSource::EnsName { name, expires_at },
+                ));
+
+                Ok(SourceResponseWithMetadata::new(result, metadata))
+            }
+        })
+        .await
+    }
+
+    async fn get_proxy_target(
+        &self,
+        chain_id: ChainID,
+        proxy_address: Address,
+        slot: ProxySlot,
+    ) -> Result<SourceResponseWithMetadata, BadgifyError> {
+        let chain = self
+            .chain_list
+            .get_chain(chain_id)
+            .ok_or(BadgifyError::UnknownChain(chain_id))?;
+        let target = self.read_proxy_slot(chain_id, proxy_address, slot).await?;
+
+        let result = SourceResponse::Address { value: target };
+        let metadata = SourceMetadata::Evm(EvmMetadata::new(
+            chain,
+            EvmSource::Proxy {
+                proxy_address,
+                slot,
+                target,
+            },
+        ));
+
+        Ok(SourceResponseWithMetadata::new(result, metadata))
+    }
+
+    async fn get_contract_owner(
+        &self,
+        chain_id: ChainID,
+        contract_address: Address,
+    ) -> Result<SourceResponseWithMetadata, BadgifyError> {
+        let chain = self
+            .chain_list
+            .get_chain(chain_id)
+            .ok_or(BadgifyError::UnknownChain(chain_id))?;
+        let owner = self.read_owner(chain_id, contract_address).await?;
+
+        let result = SourceResponse::Address { value: owner };
+        let metadata = SourceMetadata::Evm(EvmMetadata::new(
+            chain,
+            EvmSource::Owner {
+                contract_address,
+                owner,
+            },
+        ));
+
+        Ok(SourceResponseWithMetadata::new(result, metadata))
+    }
+
+    async fn get_storage(
+        &self,
+        chain_id: ChainID,
+        contract_address: Address,
+        slot: U256,
+        kind: StorageKind,
+    ) -> Result<SourceResponseWithMetadata, BadgifyError> {
+        self.read_state_with_provider(chain_id, move |chain, provider, block| async move {
+            let word = provider
+                .get_storage_at(contract_address, slot)
+                .block_id(block)
+                .await?;
+
+            let result = match kind {
+                StorageKind::Uint => SourceResponse::Integer { value: word },
+                StorageKind::Address => SourceResponse::Address {
+                    value: Address::from_word(word.into()),
+                },
+                StorageKind::Bytes => SourceResponse::AlphaNumeric {
+                    value: B256::from(word).to_string(),
+                },
+            };
+
+            let metadata = SourceMetadata::Evm(EvmMetadata::new(
+                chain,
+                EvmSource::Storage {
+                    contract_address,
+                    slot,
+                    kind,
+                },
+            ));
+
+            Ok(SourceResponseWithMetadata::new(result, metadata))
+        })
+        .await
+    }
+
+    async fn get_code(
+        &self,
+        chain_id: ChainID,
+        address: Address,
+        kind: CodeKind,
+    ) -> Result<SourceResponseWithMetadata, BadgifyError> {
+        self.read_state_with_provider(chain_id, move |chain, provider, block| async move {
+            let size = provider.get_code_at(address).block_id(block).await?.len();
+
+            let result = match kind {
+                CodeKind::Size => SourceResponse::Integer {
+                    value: U256::from(size),
+                },
+                CodeKind::IsContract => SourceResponse::Boolean { value: size > 0 },
+            };
+
+            let metadata = SourceMetadata::Evm(EvmMetadata::new(
+                chain,
+                EvmSource::Code {
+                    address,
+                    kind,
+                    size,
+                },
+            ));
+
+            Ok(SourceResponseWithMetadata::new(result, metadata))
+        })
+        .await
+    }
+
+    async fn get_chain_status(
+        &self,
+        chain_id: ChainID,
+        metric: ChainMetric,
+    ) -> Result<SourceResponseWithMetadata, BadgifyError> {
+        self.try_with_rpc_urls_provider(chain_id, move |chain, provider| async move {
+            let result = match metric {
+                ChainMetric::BlockNumber => SourceResponse::Integer {
+                    value: U256::from(provider.get_block_number().await?),
+                },
+                ChainMetric::BlockAge => {
+                    let block = provider
+                        .get_block_by_number(
+                            BlockNumberOrTag::Latest,
+                            BlockTransactionsKind::Hashes,
+                        )
+                        .await?
+                        .ok_or_else(|| {
+                            BadgifyError::Upstream("Latest block not found".to_string())
+                        })?;
+
+                    SourceResponse::Duration {
+                        seconds: unix_now().saturating_sub(block.header.timestamp),
                     }
-                    err => Err(format!("Failed to get balance or decimals {err:?}").into()),
                 }
+                ChainMetric::GasPrice => SourceResponse::Decimal {
+                    value: U256::from(provider.get_gas_price().await?),
+                    decimals: GWEI_DECIMALS,
+                },
+                ChainMetric::BaseFee => {
+                    let block = provider
+                        .get_block_by_number(
+                            BlockNumberOrTag::Latest,
+                            BlockTransactionsKind::Hashes,
+                        )
+                        .await?
+                        .ok_or_else(|| {
+                            BadgifyError::Upstream("Latest block not found".to_string())
+                        })?;
+
+                    SourceResponse::Decimal {
+                        value: U256::from(block.header.base_fee_per_gas.ok_or_else(|| {
+                            BadgifyError::Unavailable("Chain does not support EIP-1559".to_string())
+                        })?),
+                        decimals: GWEI_DECIMALS,
+                    }
+                }
+                ChainMetric::PriorityFee => SourceResponse::Decimal {
+                    value: U256::from(provider.get_max_priority_fee_per_gas().await?),
+                    decimals: GWEI_DECIMALS,
+                },
+            };
+
+            let metadata =
+                SourceMetadata::Evm(EvmMetadata::new(chain, EvmSource::ChainStatus { metric }));
+
+            Ok(SourceResponseWithMetadata::new(result, metadata))
+        })
+        .await
+    }
+
+    async fn get_tx_count(
+        &self,
+        chain_id: ChainID,
+        address: Address,
+        pending: bool,
+    ) -> Result<SourceResponseWithMetadata, BadgifyError> {
+        self.read_state_with_provider(chain_id, move |chain, provider, block| async move {
+            let tx_count = provider.get_transaction_count(address);
+            let tx_count = if pending {
+                tx_count.pending().await?
             } else {
-                Err("RPC batch request failed".into())
+                tx_count.block_id(block).await?
+            };
+
+            let result = SourceResponse::Integer {
+                value: U256::from(tx_count),
+            };
+
+            let metadata = SourceMetadata::Evm(EvmMetadata::new(
+                chain,
+                EvmSource::TxCount { address, pending },
+            ));
+
+            Ok(SourceResponseWithMetadata::new(result, metadata))
+        })
+        .await
+    }
+
+    async fn get_erc20_flow(
+        &self,
+        chain_id: ChainID,
+        contract_address: Address,
+        address: Address,
+        metric: FlowMetric,
+        window: BlockWindow,
+    ) -> Result<SourceResponseWithMetadata, BadgifyError> {
+        let multicall = self.multicall.clone();
+        let websockets = self.websockets.clone();
+
+        self.try_with_rpc_urls(chain_id, move |chain, rpc_url| {
+            let multicall = multicall.clone();
+            let websockets = websockets.clone();
+            async move {
+                let client = connect(&websockets, &rpc_url)?;
+                let provider = ProviderBuilder::default().on_client(client.clone());
+
+                let (from_block, to_block) = evm_logs::resolve_window(&provider, window).await?;
+                let totals = evm_logs::scan_transfers(
+                    &provider,
+                    contract_address,
+                    address,
+                    from_block,
+                    to_block,
+                )
+                .await?;
+
+                // Read at the end of the window, like the transfers.
+                let calls = [
+                    ContractCall::new(contract_address, &ERC20::decimalsCall::new(())),
+                    ContractCall::new(contract_address, &ERC20::symbolCall::new(())),
+                ];
+                let results = multicall
+                    .call(chain_id, &client, &calls, BlockId::number(to_block))
+                    .await?;
+
+                let decimals = evm_multicall::decode::<ERC20::decimalsCall>(&results[0])?.decimals;
+                let symbol = evm_multicall::decode::<ERC20::symbolCall>(&results[1])
+                    .map(|symbol| symbol.symbol)
+                    .unwrap_or_default();
+
+                let result = match metric {
+                    FlowMetric::Inflow => SourceResponse::Decimal {
+                        value: totals.inflow,
+                        decimals,
+                    },
+                    FlowMetric::Outflow => SourceResponse::Decimal {
+                        value: totals.outflow,
+                        decimals,
+                    },
+                    FlowMetric::Net => SourceResponse::SignedDecimal {
+                        value: I256::try_from(totals.inflow)? - I256::try_from(totals.outflow)?,
+                        decimals,
+                    },
+                    FlowMetric::Count => SourceResponse::Integer {
+                        value: U256::from(totals.count),
+                    },
+                };
+
+                let metadata = SourceMetadata::Evm(EvmMetadata::new(
+                    chain,
+                    EvmSource::ERC20Flow {
+                        symbol,
+                        contract_address,
+                        address,
+                        metric,
+                        window,
+                        from_block,
+                        to_block,
+                    },
+                ));
+
+                Ok(SourceResponseWithMetadata::new(result, metadata))
+            }
+        })
+        .await
+    }
+
+    async fn get_erc20_holders(
+        &self,
+        chain_id: ChainID,
+        contract_address: Address,
+    ) -> Result<SourceResponseWithMetadata, BadgifyError> {
+        let holder_index = self
+            .holder_index
+            .as_ref()
+            .filter(|index| index.is_indexed(chain_id, contract_address))
+            .ok_or_else(|| {
+                BadgifyError::Unavailable(format!(
+                    "Token {contract_address} is not indexed on chain {chain_id}"
+                ))
+            })?;
+
+        let count = holder_index
+            .blocking(move |index| index.holder_count(chain_id, contract_address))
+            .await?
+            .ok_or_else(|| {
+                BadgifyError::Unavailable("Token indexing has not started yet".to_string())
+            })?;
+
+        let chain = self
+            .chain_list
+            .get_chain(chain_id)
+            .ok_or(BadgifyError::UnknownChain(chain_id))?;
+
+        let result = SourceResponse::Integer {
+            value: U256::from(count.holders),
+        };
+        let metadata = SourceMetadata::Evm(EvmMetadata::new(
+            chain,
+            EvmSource::ERC20Holders {
+                contract_address,
+                indexed_block: count.indexed_block,
+                backfilling: count.backfilling,
+            },
+        ));
+
+        Ok(SourceResponseWithMetadata::new(result, metadata))
+    }
+
+    async fn read_proxy_slot(
+        &self,
+        chain_id: ChainID,
+        proxy_address: Address,
+        slot: ProxySlot,
+    ) -> Result<Address, BadgifyError> {
+        let slot_key: U256 = match slot {
+            ProxySlot::Implementation => EIP1967_IMPLEMENTATION_SLOT.into(),
+            ProxySlot::Admin => EIP1967_ADMIN_SLOT.into(),
+        };
+
+        self.read_state_with_provider(chain_id, move |_chain, provider, block| async move {
+            let word = provider
+                .get_storage_at(proxy_address, slot_key)
+                .block_id(block)
+                .await?;
+            let target = Address::from_word(word.into());
+
+            if target.is_zero() {
+                return Err(BadgifyError::NotFound(format!(
+                    "{proxy_address} is not an EIP-1967 proxy"
+                )));
             }
+
+            Ok(target)
         })
         .await
     }
 
-    async fn try_with_rpc_urls_client<F, Fut, T>(
+    async fn read_owner(
+        &self,
+        chain_id: ChainID,
+        contract_address: Address,
+    ) -> Result<Address, BadgifyError> {
+        self.read_state_with_provider(chain_id, move |_chain, provider, block| async move {
+            let tx =
+                TransactionRequest::default()
+                    .to(contract_address)
+                    .input(TransactionInput::from(
+                        Ownable::ownerCall::new(()).abi_encode(),
+                    ));
+
+            let owner = provider.call(&tx).block(block).await?;
+            Ok(Ownable::ownerCall::abi_decode_returns(&owner, true)?.owner)
+        })
+        .await
+    }
+
+    async fn read_state_with_client<F, Fut, T>(
         &self,
         chain_id: ChainID,
         predicate: F,
-    ) -> Result<T, Box<dyn Error>>
+    ) -> Result<T, BadgifyError>
     where
-        F: Fn(Arc<EvmChain>, ReqwestClient) -> Fut + Send + Sync + Clone + 'static,
-        Fut: Future<Output = Result<T, Box<dyn Error>>> + Send + 'static,
-        T: Send + 'static,
+        F: Fn(Arc<EvmChain>, ReqwestClient, BlockId) -> Fut + Send + Sync + Clone + 'static,
+        Fut: Future<Output = Result<T, BadgifyError>> + Send + 'static,
+        T: Verifiable + Send + 'static,
     {
-        self.try_with_rpc_urls(chain_id, move |chain, rpc_url| {
+        let websockets = self.websockets.clone();
+
+        self.read_state(chain_id, move |chain, rpc_url, block| {
             let predicate = predicate.clone();
+            let websockets = websockets.clone();
             async move {
-                let client = ClientBuilder::default().http(rpc_url.parse()?);
-                predicate(chain.clone(), client).await
+                let client = connect(&websockets, &rpc_url)?;
+                predicate(chain.clone(), client, block).await
             }
         })
         .await
     }
 
-    async fn try_with_rpc_urls_provider<F, Fut, T>(
+    async fn read_state_with_provider<F, Fut, T>(
         &self,
         chain_id: ChainID,
         predicate: F,
-    ) -> Result<T, Box<dyn Error>>
+    ) -> Result<T, BadgifyError>
     where
-        F: Fn(Arc<EvmChain>, Box<dyn Provider>) -> Fut + Send + Sync + Clone + 'static,
-        Fut: Future<Output = Result<T, Box<dyn Error>>> + Send + 'static,
-        T: Send + 'static,
+        F: Fn(Arc<EvmChain>, Box<dyn Provider>, BlockId) -> Fut + Send + Sync + Clone + 'static,
+        Fut: Future<Output = Result<T, BadgifyError>> + Send + 'static,
+        T: Verifiable + Send + 'static,
     {
-        self.try_with_rpc_urls(chain_id, move |chain, rpc_url| {
+        let websockets = self.websockets.clone();
+
+        self.read_state(chain_id, move |chain, rpc_url, block| {
             let predicate = predicate.clone();
+            let websockets = websockets.clone();
             async move {
-                let provider = ProviderBuilder::default().on_http(rpc_url.parse()?);
-                predicate(chain.clone(), Box::new(provider)).await
+                let provider =
+                    ProviderBuilder::default().on_client(connect(&websockets, &rpc_url)?);
+                predicate(chain.clone(), Box::new(provider), block).await
             }
         })
         .await
     }
 
-    async fn try_with_rpc_urls<'a, F, Fut, T>(
+    /// Reads chain state at the latest block from the first endpoint that
+    /// answers, or, when a quorum is configured, from several endpoints at the
+    /// same block, returning an [`evm_quorum::QuorumError`] if their answers
+    /// conflict and too few agree, or the last endpoint error if too few answer.
+    /// The quorum is decided as soon as the outcome is settled, and must be
+    /// within the request deadline.
+    async fn read_state<F, Fut, T>(
         &self,
         chain_id: ChainID,
         predicate: F,
-    ) -> Result<T, Box<dyn Error>>
+    ) -> Result<T, BadgifyError>
     where
-        F: Fn(Arc<EvmChain>, String) -> Fut + Send + Sync + Clone + 'static,
-        Fut: Future<Output = Result<T, Box<dyn Error>>> + Send + 'static,
-        T: Send + 'static,
+        F: Fn(Arc<EvmChain>, String, BlockId) -> Fut + Send + Sync + Clone + 'static,
+        Fut: Future<Output = Result<T, BadgifyError>> + Send + 'static,
+        T: Verifiable + Send + 'static,
     {
-        let chain = Arc::new(
-            self.chain_list
-                .fetch_evm_chain(chain_id)
-                .await?
-                .ok_or("Chain not found!")?,
-        );
-
-        let mut rpc_urls = chain.rpc.clone();
-        rpc_urls.shuffle(&mut rand::rng());
-
-        if let Some(rpc_url) = self.get_good_rpc_url(chain_id).await {
-            rpc_urls.insert(0, rpc_url);
+        let Some(quorum) = &self.rpc_config.quorum else {
+            return self
+                .try_with_rpc_urls(chain_id, move |chain, rpc_url| {
+                    predicate(chain, rpc_url, BlockId::latest())
+                })
+                .await;
+        };
+
+        let chain = self
+            .chain_list
+            .get_chain(chain_id)
+            .ok_or(BadgifyError::UnknownChain(chain_id))?;
+
+        // Endpoints a few blocks behind can still answer for a block that is
+        // not the very latest.
+        let latest = self
+            .try_with_rpc_urls_provider(chain_id, |_chain, provider| async move {
+                Ok(provider.get_block_number().await?)
+            })
+            .await?;
+        let block_number = latest.saturating_sub(quorum.confirmations);
+        let block = BlockId::number(block_number);
+
+        let mut rpc_urls = self.ranked_rpc_urls(&chain).into_iter();
+        let deadline = self.request_deadline();
+
+        // Requests still in flight once the outcome is settled are cancelled
+        // when the set is dropped.
+        let mut in_flight = InFlight::new();
+        let pinned = move |rpc_url| predicate(chain.clone(), rpc_url, block);
+
+        for rpc_url in rpc_urls.by_ref().take(quorum.endpoints) {
+            in_flight.spawn(&pinned, rpc_url);
         }
 
-        let rpc_urls_iter = rpc_urls
-            .iter()
-            .filter(|x| !x.contains("API_KEY") && x.starts_with("http"));
+        let mut responses = Vec::new();
+        let mut last_error = None;
 
-        for rpc_url in rpc_urls_iter {
-            let predicate = predicate.clone();
+        while !evm_quorum::is_decided(&responses, quorum.required, in_flight.len()) {
+            tokio::select! {
+                Some((rpc_url, latency, result)) = in_flight.join_next() => {
+                    let failed = match result {
+                        Ok(result) => {
+                            self.rpc_health
+                                .record_success(chain_id, &rpc_url, Some(latency), None);
+                            // The URLs end up in the error if the endpoints disagree.
+                            responses.push((self.rpc_endpoints.redact(&rpc_url), result));
+                            false
+                        }
+
+                        Err(err @ BadgifyError::Internal(_)) => {
+                            println!(
+                                "Request to {} failed: {err}",
+                                self.rpc_endpoints.redact(&rpc_url)
+                            );
+                            last_error = Some(err);
+                            true
+                        }
 
-            match predicate(chain.clone(), rpc_url.to_string()).await {
-                Ok(result) => {
-                    self.set_good_rpc_url(chain_id, rpc_url).await;
-                    return Ok(result);
+                        Err(err) if !err.is_endpoint_failure() => return Err(err),
+
+                        Err(err) => {
+                            println!(
+                                "Error with provider at {}: {}",
+                                self.rpc_endpoints.redact(&rpc_url),
+                                self.rpc_endpoints.redact(&err.to_string())
+                            );
+                            self.rpc_health.record_failure(chain_id, &rpc_url);
+                            last_error = Some(err);
+                            true
+                        }
+                    };
+
+                    // Replace the failed endpoint so that a few broken ones
+                    // do not prevent a quorum.
+                    if failed {
+                        if let Some(rpc_url) = rpc_urls.next() {
+                            in_flight.spawn(&pinned, rpc_url);
+                        }
+                    }
                 }
 
-                Err(err) => println!("Error with provider at {}: {:?}", rpc_url, err),
+                _ = sleep_until_deadline(deadline), if deadline.is_some() => {
+                    for rpc_url in in_flight.urls() {
+                        self.rpc_health.record_failure(chain_id, rpc_url);
+                    }
+
+                    return Err(BadgifyError::UpstreamTimeout);
+                }
             }
         }
 
-        Err("No active RPC URLs for chain".into())
+        evm_quorum::tally(responses, quorum.required, block_number, last_error)
     }
 
-    async fn set_good_rpc_url(&self, chain_id: ChainID, rpc_url: &str) {
-        let should_update = match self.last_known_good_rpc_urls.read().await.get(&chain_id) {
-            Some(existing) => existing != rpc_url,
-            None => true,
+    /// Returns the HTTP operator endpoints of `chain` and the public HTTP
+    /// endpoints from chainlist that can be used without an API key, if enabled.
+    fn rpc_urls(&self, chain: &EvmChain) -> (Vec<String>, Vec<String>) {
+        let operator_urls = self
+            .rpc_endpoints
+            .urls(chain.chain_id)
+            .iter()
+            .filter(|x| x.starts_with("http"))
+            .cloned()
+            .collect();
+
+        let public_urls = if self.rpc_endpoints.use_chainlist(chain.chain_id) {
+            chain
+                .rpc
+                .iter()
+                .filter(|x| !x.contains("API_KEY") && x.starts_with("http"))
+                .cloned()
+                .collect()
+        } else {
+            Vec::new()
         };
 
-        if should_update {
-            self.last_known_good_rpc_urls
-                .write()
-                .await
-                .insert(chain_id, rpc_url.to_string());
+        (operator_urls, public_urls)
+    }
+
+    /// Returns the WebSocket endpoints of `chain`, operator endpoints first.
+    fn websocket_urls(&self, chain: &EvmChain) -> Vec<String> {
+        let is_websocket = |url: &&String| url.starts_with("ws");
+
+        let mut urls: Vec<String> = self
+            .rpc_endpoints
+            .urls(chain.chain_id)
+            .iter()
+            .filter(is_websocket)
+            .cloned()
+            .collect();
+
+        if self.rpc_endpoints.use_chainlist(chain.chain_id) {
+            urls.extend(
+                chain
+                    .rpc
+                    .iter()
+                    .filter(|x| !x.contains("API_KEY"))
+                    .filter(is_websocket)
+                    .cloned(),
+            );
         }
+
+        urls
     }
 
-    async fn get_good_rpc_url(&self, chain_id: ChainID) -> Option<String> {
-        self.last_known_good_rpc_urls
-            .read()
+    /// Orders the endpoints of `chain` by preference. An open WebSocket
+    /// connection comes first and operator endpoints before public ones, but
+    /// ejected endpoints come after every other one.
+    fn ranked_rpc_urls(&self, chain: &EvmChain) -> Vec<String> {
+        let chain_id = chain.chain_id;
+        let (operator_urls, mut public_urls) = self.rpc_urls(chain);
+        public_urls.shuffle(&mut rand::rng());
+
+        let mut rpc_urls: Vec<String> = self
+            .websockets
+            .connected_url(chain_id)
+            .into_iter()
+            .collect();
+        rpc_urls.extend(self.rpc_health.rank(chain_id, operator_urls));
+        rpc_urls.extend(self.rpc_health.rank(chain_id, public_urls));
+        rpc_urls.sort_by_key(|rpc_url| !self.rpc_health.is_available(chain_id, rpc_url));
+
+        rpc_urls
+    }
+
+    pub(super) async fn try_with_rpc_urls_provider<F, Fut, T>(
+        &self,
+        chain_id: ChainID,
+        predicate: F,
+    ) -> Result<T, BadgifyError>
+    where
+        F: Fn(Arc<EvmChain>, Box<dyn Provider>) -> Fut + Send + Sync + Clone + 'static,
+        Fut: Future<Output = Result<T, BadgifyError>> + Send + 'static,
+        T: Send + 'static,
+    {
+        let websockets = self.websockets.clone();
+
+        self.try_with_rpc_urls(chain_id, move |chain, rpc_url| {
+            let predicate = predicate.clone();
+            let websockets = websockets.clone();
+            async move {
+                let provider =
+                    ProviderBuilder::default().on_client(connect(&websockets, &rpc_url)?);
+                predicate(chain.clone(), Box::new(provider)).await
+            }
+        })
+        .await
+    }
+
+    /// Returns when a request started now times out, if requests have a deadline.
+    fn request_deadline(&self) -> Option<tokio::time::Instant> {
+        self.rpc_config
+            .request_timeout_secs
+            .map(|secs| tokio::time::Instant::now() + Duration::from_secs(secs))
+    }
+
+    async fn try_with_rpc_urls<F, Fut, T>(
+        &self,
+        chain_id: ChainID,
+        predicate: F,
+    ) -> Result<T, BadgifyError>
+    where
+        F: Fn(Arc<EvmChain>, String) -> Fut + Send + Sync + Clone + 'static,
+        Fut: Future<Output = Result<T, BadgifyError>> + Send + 'static,
+        T: Send + 'static,
+    {
+        let chain = self
+            .chain_list
+            .get_chain(chain_id)
+            .ok_or(BadgifyError::UnknownChain(chain_id))?;
+
+        let hedging = Hedging {
+            chain_id,
+            rpc_health: &self.rpc_health,
+            redact: &|text| self.rpc_endpoints.redact(text),
+            max_hedges: self.rpc_config.max_hedges,
+            hedge_delay: Duration::from_millis(self.rpc_config.hedge_delay_ms),
+            deadline: self.request_deadline(),
+        };
+
+        hedging
+            .run(self.ranked_rpc_urls(&chain), move |rpc_url| {
+                predicate(chain.clone(), rpc_url)
+            })
             .await
-            .get(&chain_id)
-            .cloned()
+    }
+}
+
+/// Returns the open WebSocket connection for `ws://` and `wss://` URLs and a
+/// new HTTP client otherwise.
+fn connect(websockets: &WsConnections, rpc_url: &str) -> Result<ReqwestClient, BadgifyError> {
+    if rpc_url.starts_with("ws") {
+        return websockets
+            .client(rpc_url)
+            .ok_or_else(|| BadgifyError::Upstream("WebSocket connection was lost".to_string()));
+    }
+
+    let rpc_url = rpc_url
+        .parse()
+        .map_err(|err| BadgifyError::Upstream(format!("Invalid RPC URL: {err}")))?;
+
+    Ok(ClientBuilder::default().http(rpc_url))
+}
+
+/// Measures how long `rpc_url` takes to return its latest block number.
+async fn probe_rpc_url(rpc_health: Arc<RpcHealth>, chain_id: ChainID, rpc_url: String) {
+    let Ok(url) = rpc_url.parse() else {
+        return;
+    };
+
+    let client = ClientBuilder::default().http(url);
+    let started_at = Instant::now();
+    let request = client.request_noparams::<U64>("eth_blockNumber");
+
+    match tokio::time::timeout(PROBE_TIMEOUT, request).await {
+        Ok(Ok(block_number)) => rpc_health.record_success(
+            chain_id,
+            &rpc_url,
+            Some(started_at.elapsed()),
+            Some(block_number.to()),
+        ),
+        _ => rpc_health.record_failure(chain_id, &rpc_url),
     }
 }
@@ -0,0 +1,192 @@
+use std::{collections::HashMap, future::Future, time::Duration};
+
+use tokio::{
+    task::{Id, JoinSet},
+    time::Instant,
+};
+
+use crate::{error::BadgifyError, types::ChainID};
+
+use super::rpc_health::RpcHealth;
+
+/// The endpoint a request went to, how long it took and its result.
+pub type Finished<T> = (String, Duration, Result<T, BadgifyError>);
+
+/// Requests to the endpoints of a chain, raced as tasks so that the losers
+/// are cancelled when the set is dropped. Tasks are tracked by id, since one
+/// that panics does not say which endpoint it was for.
+pub struct InFlight<T> {
+    requests: JoinSet<(Duration, Result<T, BadgifyError>)>,
+    urls: HashMap<Id, String>,
+}
+
+impl<T: Send + 'static> InFlight<T> {
+    pub fn new() -> Self {
+        Self {
+            requests: JoinSet::new(),
+            urls: HashMap::new(),
+        }
+    }
+
+    pub fn len(&self) -> usize {
+        self.requests.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.requests.is_empty()
+    }
+
+    pub fn urls(&self) -> impl Iterator<Item = &str> {
+        self.urls.values().map(String::as_str)
+    }
+
+    /// Runs `predicate` against `rpc_url` as a task, timing it.
+    pub fn spawn<F, Fut>(&mut self, predicate: &F, rpc_url: String)
+    where
+        F: Fn(String) -> Fut,
+        Fut: Future<Output = Result<T, BadgifyError>> + Send + 'static,
+    {
+        let request = predicate(rpc_url.clone());
+        let handle = self.requests.spawn(async move {
+            let started = Instant::now();
+            let result = request.await;
+            (started.elapsed(), result)
+        });
+        self.urls.insert(handle.id(), rpc_url);
+    }
+
+    /// Waits for the next request to finish. A task that panicked counts as
+    /// an internal error, not as a failure of its endpoint.
+    pub async fn join_next(&mut self) -> Option<Finished<T>> {
+        let joined = self.requests.join_next_with_id().await?;
+
+        Some(match joined {
+            Ok((id, (latency, result))) => {
+                (self.urls.remove(&id).unwrap_or_default(), latency, result)
+            }
+            Err(err) => {
+                let rpc_url = self.urls.remove(&err.id()).unwrap_or_default();
+                (
+                    rpc_url,
+                    Duration::ZERO,
+                    Err(BadgifyError::Internal(format!(
+                        "Request task failed: {err}"
+                    ))),
+                )
+            }
+        })
+    }
+}
+
+impl<T: Send + 'static> Default for InFlight<T> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// How requests are spread across the endpoints of a chain.
+pub struct Hedging<'a> {
+    pub chain_id: ChainID,
+    pub rpc_health: &'a RpcHealth,
+    /// Applied to errors before they are logged, as they may repeat URLs.
+    pub redact: &'a (dyn Fn(&str) -> String + Sync),
+    pub max_hedges: usize,
+    pub hedge_delay: Duration,
+    pub deadline: Option<Instant>,
+}
+
+impl Hedging<'_> {
+    /// Runs `predicate` against `rpc_urls` in order until one succeeds. When
+    /// hedging is enabled, an endpoint that has not answered within the hedge
+    /// delay is raced against the next one and the first answer wins. Errors
+    /// that every endpoint would return, such as reverts, are returned
+    /// straight away. Past the deadline, the endpoints still in flight are
+    /// recorded as failed.
+    pub async fn run<F, Fut, T>(
+        &self,
+        rpc_urls: Vec<String>,
+        predicate: F,
+    ) -> Result<T, BadgifyError>
+    where
+        F: Fn(String) -> Fut,
+        Fut: Future<Output = Result<T, BadgifyError>> + Send + 'static,
+        T: Send + 'static,
+    {
+        let mut rpc_urls = rpc_urls.into_iter();
+
+        // Without hedging at most one request is in flight.
+        let mut in_flight = InFlight::new();
+        let mut last_error = None;
+
+        loop {
+            if in_flight.is_empty() {
+                let Some(rpc_url) = rpc_urls.next() else {
+                    break;
+                };
+
+                in_flight.spawn(&predicate, rpc_url);
+            }
+
+            let can_hedge = in_flight.len() <= self.max_hedges && rpc_urls.len() > 0;
+
+            tokio::select! {
+                Some((rpc_url, latency, result)) = in_flight.join_next() => {
+                    match result {
+                        Ok(result) => {
+                            self.rpc_health
+                                .record_success(self.chain_id, &rpc_url, Some(latency), None);
+                            return Ok(result);
+                        }
+
+                        Err(err @ BadgifyError::Internal(_)) => {
+                            println!("Request to {} failed: {err}", (self.redact)(&rpc_url));
+                            last_error = Some(err);
+                        }
+
+                        // Every endpoint would give the same answer.
+                        Err(err) if !err.is_endpoint_failure() => {
+                            self.rpc_health
+                                .record_success(self.chain_id, &rpc_url, Some(latency), None);
+                            return Err(err);
+                        }
+
+                        Err(err) => {
+                            println!(
+                                "Error with provider at {}: {}",
+                                (self.redact)(&rpc_url),
+                                (self.redact)(&err.to_string())
+                            );
+                            self.rpc_health.record_failure(self.chain_id, &rpc_url);
+                            last_error = Some(err);
+                        }
+                    }
+                }
+
+                _ = tokio::time::sleep(self.hedge_delay), if can_hedge => {
+                    if let Some(rpc_url) = rpc_urls.next() {
+                        in_flight.spawn(&predicate, rpc_url);
+                    }
+                }
+
+                _ = sleep_until_deadline(self.deadline), if self.deadline.is_some() => {
+                    for rpc_url in in_flight.urls() {
+                        self.rpc_health.record_failure(self.chain_id, rpc_url);
+                    }
+
+                    return Err(BadgifyError::UpstreamTimeout);
+                }
+            }
+        }
+
+        Err(last_error.unwrap_or_else(|| {
+            BadgifyError::NoRpc(format!("No active RPC URLs for chain {}", self.chain_id))
+        }))
+    }
+}
+
+pub async fn sleep_until_deadline(deadline: Option<Instant>) {
+    match deadline {
+        Some(deadline) => tokio::time::sleep_until(deadline).await,
+        None => std::future::pending().await,
+    }
+}
@@ -0,0 +1,435 @@
+use std::{
+    collections::HashSet,
+    str::FromStr,
+    sync::{Arc, Mutex},
+    time::Duration,
+};
+
+use alloy::primitives::{Address, U256};
+use rusqlite::{params, Connection, OptionalExtension};
+
+use crate::{
+    config::{IndexedToken, IndexerConfig},
+    error::BadgifyError,
+    types::ChainID,
+};
+
+use super::{evm::EvmDataSource, evm::ERC20, evm_logs};
+
+// Blocks fetched and committed per step, so a long backfill makes visible progress.
+const INDEX_STEP_SIZE: u64 = 10_000;
+
+/// A `Transfer` event of an indexed token.
+#[derive(Debug, Clone)]
+pub struct IndexedTransfer {
+    pub block_number: u64,
+    pub from: Address,
+    pub to: Address,
+    pub value: U256,
+}
+
+/// The holders of an indexed token as of `indexed_block`. The count is
+/// partial while the index is still catching up with the chain head.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct HolderCount {
+    pub holders: u64,
+    pub indexed_block: u64,
+    pub backfilling: bool,
+}
+
+/// Follows the `Transfer` events of configured tokens and keeps their holder
+/// balances in a local SQLite database. Database work blocks, so async code
+/// goes through [`HolderIndex::blocking`].
+pub struct HolderIndex {
+    store: Mutex<Connection>,
+    config: IndexerConfig,
+    /// Tokens whose index reached the chain head since startup.
+    synced: Mutex<HashSet<(String, String)>>,
+}
+
+impl HolderIndex {
+    pub fn open(config: IndexerConfig) -> Result<Self, BadgifyError> {
+        let connection = Connection::open(&config.database_path)?;
+
+        connection.execute_batch(
+            "CREATE TABLE IF NOT EXISTS cursors (
+                chain_id TEXT NOT NULL,
+                token TEXT NOT NULL,
+                last_block INTEGER NOT NULL,
+                PRIMARY KEY (chain_id, token)
+            );
+            CREATE TABLE IF NOT EXISTS balances (
+                chain_id TEXT NOT NULL,
+                token TEXT NOT NULL,
+                holder TEXT NOT NULL,
+                balance TEXT NOT NULL,
+                PRIMARY KEY (chain_id, token, holder)
+            );
+            CREATE TABLE IF NOT EXISTS journal (
+                chain_id TEXT NOT NULL,
+                token TEXT NOT NULL,
+                block_number INTEGER NOT NULL,
+                sender TEXT NOT NULL,
+                recipient TEXT NOT NULL,
+                value TEXT NOT NULL
+            );
+            CREATE INDEX IF NOT EXISTS journal_block
+                ON journal (chain_id, token, block_number);",
+        )?;
+
+        Ok(Self {
+            store: Mutex::new(connection),
+            config,
+            synced: Mutex::default(),
+        })
+    }
+
+    pub fn is_indexed(&self, chain_id: ChainID, token: Address) -> bool {
+        self.config
+            .tokens
+            .iter()
+            .any(|t| ChainID::from(t.chain_id) == chain_id && t.address == token)
+    }
+
+    /// Runs `f` on the blocking thread pool, so that SQLite work and waiting
+    /// for the store do not stall the async workers serving requests.
+    pub async fn blocking<T, F>(self: &Arc<Self>, f: F) -> Result<T, BadgifyError>
+    where
+        F: FnOnce(&HolderIndex) -> Result<T, BadgifyError> + Send + 'static,
+        T: Send + 'static,
+    {
+        let index = self.clone();
+
+        tokio::task::spawn_blocking(move || f(&index))
+            .await
+            .map_err(|err| BadgifyError::Internal(format!("Index task failed: {err}")))?
+    }
+
+    /// Returns the number of addresses with a non-zero balance, or `None` if
+    /// indexing has not started yet.
+    pub fn holder_count(
+        &self,
+        chain_id: ChainID,
+        token: Address,
+    ) -> Result<Option<HolderCount>, BadgifyError> {
+        let store = self.store.lock().unwrap();
+        let key = (chain_id.to_string(), token.to_string());
+
+        let Some(last_block) = get_cursor(&store, &key)? else {
+            return Ok(None);
+        };
+
+        let holders: u64 = store.query_row(
+            "SELECT COUNT(*) FROM balances WHERE chain_id = ?1 AND token = ?2",
+            params![key.0, key.1],
+            |row| row.get(0),
+        )?;
+
+        Ok(Some(HolderCount {
+            holders,
+            indexed_block: last_block,
+            backfilling: !self.synced.lock().unwrap().contains(&key),
+        }))
+    }
+
+    /// Rolls back the indexed blocks that may still be reorganised and
+    /// returns the first block to scan, which is the start block of the
+    /// token if nothing is indexed yet.
+    pub fn resume(&self, token: &IndexedToken) -> Result<u64, BadgifyError> {
+        let mut store = self.store.lock().unwrap();
+        let key = token_key(token);
+
+        let Some(last_block) = get_cursor(&store, &key)? else {
+            return Ok(token.start_block);
+        };
+
+        let from_block = (last_block + 1)
+            .saturating_sub(self.config.reorg_depth)
+            .max(token.start_block);
+
+        let tx = store.transaction()?;
+        rollback(&tx, &key, from_block)?;
+        if from_block == token.start_block {
+            tx.execute(
+                "DELETE FROM cursors WHERE chain_id = ?1 AND token = ?2",
+                params![key.0, key.1],
+            )?;
+        } else {
+            set_cursor(&tx, &key, from_block - 1)?;
+        }
+        tx.commit()?;
+
+        Ok(from_block)
+    }
+
+    /// Applies `transfers`, which must be every transfer of the token up to
+    /// `to_block` after the cursor, and moves the cursor to `to_block`.
+    pub fn commit(
+        &self,
+        token: &IndexedToken,
+        transfers: &[IndexedTransfer],
+        to_block: u64,
+    ) -> Result<(), BadgifyError> {
+        let mut store = self.store.lock().unwrap();
+        let key = token_key(token);
+        let tx = store.transaction()?;
+
+        for transfer in transfers {
+            apply_transfer(&tx, &key, transfer.from, transfer.to, transfer.value, false)?;
+
+            tx.execute(
+                "INSERT INTO journal (chain_id, token, block_number, sender, recipient, value)
+                VALUES (?1, ?2, ?3, ?4, ?5, ?6)",
+                params![
+                    key.0,
+                    key.1,
+                    transfer.block_number,
+                    transfer.from.to_string(),
+                    transfer.to.to_string(),
+                    transfer.value.to_string()
+                ],
+            )?;
+        }
+
+        set_cursor(&tx, &key, to_block)?;
+        // Only blocks that can still be rolled back need their transfers kept.
+        tx.execute(
+            "DELETE FROM journal WHERE chain_id = ?1 AND token = ?2 AND block_number < ?3",
+            params![
+                key.0,
+                key.1,
+                to_block.saturating_sub(self.config.reorg_depth)
+            ],
+        )?;
+        tx.commit()?;
+
+        Ok(())
+    }
+
+    /// Records that the index of `token` has reached the chain head, so its
+    /// holder count is no longer partial.
+    pub fn mark_synced(&self, token: &IndexedToken) {
+        self.synced.lock().unwrap().insert(token_key(token));
+    }
+
+    /// Indexes every configured token forever, polling for new blocks.
+    pub async fn run(self: Arc<Self>, data_source: Arc<EvmDataSource>) {
+        let poll_interval = Duration::from_secs(self.config.poll_interval_secs);
+
+        loop {
+            for token in &self.config.tokens {
+                if let Err(err) = self.index_token(&data_source, token).await {
+                    println!(
+                        "Failed to index token {} on chain {}: {err:?}",
+                        token.address, token.chain_id
+                    );
+                }
+            }
+
+            tokio::time::sleep(poll_interval).await;
+        }
+    }
+
+    async fn index_token(
+        self: &Arc<Self>,
+        data_source: &EvmDataSource,
+        token: &IndexedToken,
+    ) -> Result<(), BadgifyError> {
+        let chain_id = ChainID::from(token.chain_id);
+
+        let latest = data_source
+            .try_with_rpc_urls_provider(chain_id, |_chain, provider| async move {
+                Ok(provider.get_block_number().await?)
+            })
+            .await?;
+
+        // Blocks near the head may still be reorganised, so they are rolled
+        // back and scanned again on every poll.
+        let resumed_token = token.clone();
+        let mut from_block = self
+            .blocking(move |index| index.resume(&resumed_token))
+            .await?;
+
+        while from_block <= latest {
+            let to_block = (from_block + INDEX_STEP_SIZE - 1).min(latest);
+            let contract_address = token.address;
+
+            let logs = data_source
+                .try_with_rpc_urls_provider(chain_id, move |_chain, provider| async move {
+                    let filter = evm_logs::transfer_filter(contract_address);
+                    evm_logs::get_logs_chunked(provider.as_ref(), filter, from_block, to_block)
+                        .await
+                })
+                .await?;
+
+            let transfers = logs
+                .iter()
+                .map(|log| {
+                    let block_number = log.block_number.ok_or_else(|| {
+                        BadgifyError::Upstream("Log without block number".to_string())
+                    })?;
+                    let transfer = log.log_decode::<ERC20::Transfer>()?.inner.data;
+
+                    Ok(IndexedTransfer {
+                        block_number,
+                        from: transfer.from,
+                        to: transfer.to,
+                        value: transfer.value,
+                    })
+                })
+                .collect::<Result<Vec<_>, BadgifyError>>()?;
+
+            let committed_token = token.clone();
+            self.blocking(move |index| index.commit(&committed_token, &transfers, to_block))
+                .await?;
+
+            from_block = to_block + 1;
+        }
+
+        self.mark_synced(token);
+
+        Ok(())
+    }
+}
+
+fn token_key(token: &IndexedToken) -> (String, String) {
+    (
+        ChainID::from(token.chain_id).to_string(),
+        token.address.to_string(),
+    )
+}
+
+fn get_cursor(store: &Connection, key: &(String, String)) -> Result<Option<u64>, BadgifyError> {
+    Ok(store
+        .query_row(
+            "SELECT last_block FROM cursors WHERE chain_id = ?1 AND token = ?2",
+            params![key.0, key.1],
+            |row| row.get(0),
+        )
+        .optional()?)
+}
+
+fn set_cursor(
+    store: &Connection,
+    key: &(String, String),
+    last_block: u64,
+) -> Result<(), BadgifyError> {
+    store.execute(
+        "INSERT INTO cursors (chain_id, token, last_block) VALUES (?1, ?2, ?3)
+        ON CONFLICT (chain_id, token) DO UPDATE SET last_block = excluded.last_block",
+        params![key.0, key.1, last_block],
+    )?;
+
+    Ok(())
+}
+
+/// Reverts every journaled transfer at or after `from_block`.
+fn rollback(
+    store: &Connection,
+    key: &(String, String),
+    from_block: u64,
+) -> Result<(), BadgifyError> {
+    let mut statement = store.prepare(
+        "SELECT sender, recipient, value FROM journal
+        WHERE chain_id = ?1 AND token = ?2 AND block_number >= ?3",
+    )?;
+
+    let transfers = statement
+        .query_map(params![key.0, key.1, from_block], |row| {
+            Ok((
+                row.get::<_, String>(0)?,
+                row.get::<_, String>(1)?,
+                row.get::<_, String>(2)?,
+            ))
+        })?
+        .collect::<Result<Vec<_>, _>>()?;
+
+    for (from, to, value) in transfers {
+        apply_transfer(
+            store,
+            key,
+            Address::from_str(&from)?,
+            Address::from_str(&to)?,
+            U256::from_str(&value)?,
+            true,
+        )?;
+    }
+
+    store.execute(
+        "DELETE FROM journal WHERE chain_id = ?1 AND token = ?2 AND block_number >= ?3",
+        params![key.0, key.1, from_block],
+    )?;
+
+    Ok(())
+}
+
+/// Moves `value` from `from` to `to`, or back again when `revert` is set.
+/// Mints and burns touch only one side; zero balances are removed so that
+/// the row count is the holder count.
+fn apply_transfer(
+    store: &Connection,
+    key: &(String, String),
+    from: Address,
+    to: Address,
+    value: U256,
+    revert: bool,
+) -> Result<(), BadgifyError> {
+    let (debited, credited) = if revert { (to, from) } else { (from, to) };
+
+    if !debited.is_zero() {
+        let balance = get_balance(store, key, debited)?;
+        let new_balance = balance.checked_sub(value).unwrap_or_else(|| {
+            println!("Negative balance for {debited}, is start_block before deployment?");
+            U256::ZERO
+        });
+        set_balance(store, key, debited, new_balance)?;
+    }
+
+    if !credited.is_zero() {
+        let balance = get_balance(store, key, credited)?;
+        set_balance(store, key, credited, balance.saturating_add(value))?;
+    }
+
+    Ok(())
+}
+
+fn get_balance(
+    store: &Connection,
+    key: &(String, String),
+    holder: Address,
+) -> Result<U256, BadgifyError> {
+    let balance: Option<String> = store
+        .query_row(
+            "SELECT balance FROM balances WHERE chain_id = ?1 AND token = ?2 AND holder = ?3",
+            params![key.0, key.1, holder.to_string()],
+            |row| row.get(0),
+        )
+        .optional()?;
+
+    Ok(balance
+        .map(|balance| U256::from_str(&balance))
+        .transpose()?
+        .unwrap_or_default())
+}
+
+fn set_balance(
+    store: &Connection,
+    key: &(String, String),
+    holder: Address,
+    balance: U256,
+) -> Result<(), BadgifyError> {
+    if balance.is_zero() {
+        store.execute(
+            "DELETE FROM balances WHERE chain_id = ?1 AND token = ?2 AND holder = ?3",
+            params![key.0, key.1, holder.to_string()],
+        )?;
+    } else {
+        store.execute(
+            "INSERT INTO balances (chain_id, token, holder, balance) VALUES (?1, ?2, ?3, ?4)
+            ON CONFLICT (chain_id, token, holder) DO UPDATE SET balance = excluded.balance",
+            params![key.0, key.1, holder.to_string(), balance.to_string()],
+        )?;
+    }
+
+    Ok(())
+}
@@ -0,0 +1,184 @@
+use alloy::{
+    primitives::{Address, U256},
+    providers::Provider,
+    rpc::types::{BlockNumberOrTag, BlockTransactionsKind, Filter, Log},
+    sol_types::SolEvent,
+    transports::{RpcError, TransportError, TransportErrorKind},
+};
+
+use crate::{
+    error::BadgifyError,
+    query::{evm::MAX_WINDOW_BLOCKS, BlockWindow},
+};
+
+use super::evm::ERC20;
+
+// Most public RPCs cap `eth_getLogs` at a few thousand blocks or results.
+const LOG_CHUNK_SIZE: u64 = 2_000;
+const MIN_LOG_CHUNK_SIZE: u64 = 16;
+const BLOCK_TIME_SAMPLE_SIZE: u64 = 1_000;
+
+/// Totals of the `Transfer` events of a token that touch an address.
+#[derive(Debug, Default)]
+pub struct TransferTotals {
+    pub inflow: U256,
+    pub outflow: U256,
+    pub count: u64,
+}
+
+/// Resolves `window` to an inclusive `(from, to)` block range ending at the
+/// latest block. Time windows are converted using the average block time of
+/// recent blocks, and rejected if that makes them longer than
+/// [`MAX_WINDOW_BLOCKS`] on fast chains.
+pub async fn resolve_window(
+    provider: &dyn Provider,
+    window: BlockWindow,
+) -> Result<(u64, u64), BadgifyError> {
+    let latest = provider.get_block_number().await?;
+
+    let blocks = match window {
+        BlockWindow::Blocks(blocks) => blocks,
+        BlockWindow::Seconds(seconds) => {
+            let sample_start = latest.saturating_sub(BLOCK_TIME_SAMPLE_SIZE);
+            let latest_timestamp = get_block_timestamp(provider, latest).await?;
+            let sample_timestamp = get_block_timestamp(provider, sample_start).await?;
+
+            let elapsed = latest_timestamp.saturating_sub(sample_timestamp).max(1);
+            let sampled_blocks = (latest - sample_start).max(1);
+
+            seconds.checked_mul(sampled_blocks).ok_or_else(|| {
+                BadgifyError::Unavailable(format!("Window of {window} is too large"))
+            })? / elapsed
+        }
+    };
+
+    if blocks > MAX_WINDOW_BLOCKS {
+        return Err(BadgifyError::Unavailable(format!(
+            "Window of {window} spans {blocks} blocks, more than the maximum of {MAX_WINDOW_BLOCKS}"
+        )));
+    }
+
+    Ok((latest.saturating_sub(blocks), latest))
+}
+
+/// Scans the `Transfer` events of `token` from or to `address` between
+/// `from_block` and `to_block` (inclusive).
+pub async fn scan_transfers(
+    provider: &dyn Provider,
+    token: Address,
+    address: Address,
+    from_block: u64,
+    to_block: u64,
+) -> Result<TransferTotals, BadgifyError> {
+    let transfers = transfer_filter(token);
+
+    let incoming = get_logs_chunked(
+        provider,
+        transfers.clone().topic2(address.into_word()),
+        from_block,
+        to_block,
+    )
+    .await?;
+    let outgoing = get_logs_chunked(
+        provider,
+        transfers.topic1(address.into_word()),
+        from_block,
+        to_block,
+    )
+    .await?;
+
+    let mut totals = TransferTotals::default();
+
+    for log in &incoming {
+        totals.inflow += log.log_decode::<ERC20::Transfer>()?.inner.data.value;
+        totals.count += 1;
+    }
+
+    for log in &outgoing {
+        let transfer = log.log_decode::<ERC20::Transfer>()?.inner.data;
+        totals.outflow += transfer.value;
+
+        // Self transfers were already counted as incoming.
+        if transfer.to != address {
+            totals.count += 1;
+        }
+    }
+
+    Ok(totals)
+}
+
+/// Returns a filter matching every `Transfer` event of `token`.
+pub fn transfer_filter(token: Address) -> Filter {
+    Filter::new()
+        .address(token)
+        .event_signature(ERC20::Transfer::SIGNATURE_HASH)
+}
+
+/// Fetches the logs matching `filter` in chunks, halving the chunk size
+/// whenever the provider rejects a range as too large. Such limits are fixed
+/// per provider, so the chunk size does not grow back. Other errors fail the
+/// scan, leaving retries to the next endpoint.
+pub async fn get_logs_chunked(
+    provider: &dyn Provider,
+    filter: Filter,
+    from_block: u64,
+    to_block: u64,
+) -> Result<Vec<Log>, BadgifyError> {
+    let mut logs = Vec::new();
+    let mut chunk_size = LOG_CHUNK_SIZE;
+    let mut chunk_start = from_block;
+
+    while chunk_start <= to_block {
+        let chunk_end = (chunk_start + chunk_size - 1).min(to_block);
+        let chunk_filter = filter.clone().from_block(chunk_start).to_block(chunk_end);
+
+        match provider.get_logs(&chunk_filter).await {
+            Ok(chunk_logs) => {
+                logs.extend(chunk_logs);
+                chunk_start = chunk_end + 1;
+            }
+            Err(err) if chunk_size > MIN_LOG_CHUNK_SIZE && is_range_error(&err) => {
+                println!("eth_getLogs rejected {chunk_size} blocks, retrying smaller: {err}");
+                chunk_size /= 2;
+            }
+            Err(err) => return Err(err.into()),
+        }
+    }
+
+    Ok(logs)
+}
+
+/// Whether the provider rejected an `eth_getLogs` request for spanning too
+/// many blocks or matching too many logs. Providers word this differently,
+/// e.g. "block range is too wide" or "query returned more than 10000 results".
+pub fn is_range_error(err: &TransportError) -> bool {
+    const RANGE_ERRORS: [&str; 6] = [
+        "range",
+        "too many",
+        "too large",
+        "more than",
+        "exceed",
+        "response size",
+    ];
+
+    match err {
+        RpcError::ErrorResp(payload) => {
+            let message = payload.message.to_lowercase();
+            RANGE_ERRORS.iter().any(|error| message.contains(error))
+        }
+        RpcError::Transport(TransportErrorKind::HttpError(http_err)) => http_err.status == 413,
+        _ => false,
+    }
+}
+
+async fn get_block_timestamp(provider: &dyn Provider, number: u64) -> Result<u64, BadgifyError> {
+    let block = provider
+        .get_block_by_number(
+            BlockNumberOrTag::Number(number),
+            BlockTransactionsKind::Hashes,
+        )
+        .await?
+        .ok_or_else(|| BadgifyError::Upstream(format!("Block {number} not found")))?;
+
+    Ok(block.header.timestamp)
+}
@@ -1,32 +1,123 @@
 use std::sync::Arc;
 
-use serde::Serialize;
+use alloy::primitives::U256;
+use serde::{Deserialize, Serialize};
 
-use crate::{evm_chainlist::EvmChain, types::EvmAddress};
+use crate::{
+    evm_chainlist::EvmChain,
+    query::{BlockWindow, ChainMetric, FlowMetric, ProxySlot, SafeField, StorageKind},
+    types::{ChainID, EvmAddress},
+};
 
-#[derive(Serialize)]
+#[derive(Serialize, Deserialize)]
 pub struct EvmMetadata {
     pub chain: Arc<EvmChain>,
+    pub testnet: bool,
+    pub deprecated: bool,
+    pub eip1559: bool,
+    pub parent_chain_id: Option<ChainID>,
     pub source: EvmSource,
 }
 
 impl EvmMetadata {
     pub fn new(chain: Arc<EvmChain>, source: EvmSource) -> Self {
-        Self { chain, source }
+        Self {
+            testnet: chain.is_testnet(),
+            deprecated: chain.is_deprecated(),
+            eip1559: chain.supports_eip1559(),
+            parent_chain_id: chain.parent_chain_id(),
+            chain,
+            source,
+        }
     }
 
-    pub fn symbol(&self) -> String {
+    pub fn symbol(&self) -> Option<String> {
         match &self.source {
-            EvmSource::NativeCurrency { symbol } => symbol.to_string(),
+            EvmSource::NativeCurrency { symbol } => Some(symbol.to_string()),
             EvmSource::ERC20 {
                 symbol,
                 contract_address: _,
-            } => symbol.to_string(),
+            } => Some(symbol.to_string()),
+            EvmSource::Safe { field, .. } => match field {
+                SafeField::Owners => Some("owners".to_string()),
+                SafeField::Threshold | SafeField::Nonce => None,
+            },
+            EvmSource::EnsName { .. } => None,
+            EvmSource::Proxy { .. } => None,
+            EvmSource::Owner { .. } => None,
+            EvmSource::Storage { .. } => None,
+            EvmSource::Code { kind, .. } => match kind {
+                CodeKind::Size => Some("bytes".to_string()),
+                CodeKind::IsContract => None,
+            },
+            EvmSource::ChainStatus { metric } => match metric {
+                ChainMetric::GasPrice | ChainMetric::BaseFee | ChainMetric::PriorityFee => {
+                    Some("gwei".to_string())
+                }
+                ChainMetric::BlockNumber | ChainMetric::BlockAge => None,
+            },
+            EvmSource::TxCount { .. } => None,
+            EvmSource::ERC20Flow { symbol, metric, .. } => match metric {
+                FlowMetric::Count => Some("transfers".to_string()),
+                _ => Some(symbol.to_string()),
+            },
+            EvmSource::ERC20Holders { .. } => None,
         }
     }
 
     pub fn label(&self) -> Option<String> {
-        Some(self.chain.name.clone())
+        match &self.source {
+            EvmSource::Safe { field, .. } => match field {
+                SafeField::Threshold | SafeField::Owners => {
+                    Some(format!("{} Safe", self.chain.name))
+                }
+                SafeField::Nonce => Some(format!("{} Safe nonce", self.chain.name)),
+            },
+            EvmSource::EnsName { name, .. } => Some(format!("{name} expiry")),
+            EvmSource::Proxy { slot, .. } => match slot {
+                ProxySlot::Implementation => Some(format!("{} implementation", self.chain.name)),
+                ProxySlot::Admin => Some(format!("{} proxy admin", self.chain.name)),
+            },
+            EvmSource::Owner { .. } => Some(format!("{} owner", self.chain.name)),
+            EvmSource::Storage { .. } => Some(format!("{} storage", self.chain.name)),
+            EvmSource::Code { kind, .. } => match kind {
+                CodeKind::Size => Some(format!("{} code size", self.chain.name)),
+                CodeKind::IsContract => Some(format!("{} contract", self.chain.name)),
+            },
+            EvmSource::ChainStatus { metric } => {
+                let metric = match metric {
+                    ChainMetric::BlockNumber => "block",
+                    ChainMetric::BlockAge => "block age",
+                    ChainMetric::GasPrice => "gas price",
+                    ChainMetric::BaseFee => "base fee",
+                    ChainMetric::PriorityFee => "priority fee",
+                };
+
+                Some(format!("{} {metric}", self.chain.name))
+            }
+            EvmSource::TxCount { pending, .. } => match pending {
+                true => Some(format!("{} transactions sent (pending)", self.chain.name)),
+                false => Some(format!("{} transactions sent", self.chain.name)),
+            },
+            EvmSource::ERC20Flow { metric, window, .. } => {
+                let metric = match metric {
+                    FlowMetric::Inflow => "inflow",
+                    FlowMetric::Outflow => "outflow",
+                    FlowMetric::Net => "net flow",
+                    FlowMetric::Count => "transfers",
+                };
+
+                Some(format!("{} {metric} ({window})", self.chain.name))
+            }
+            EvmSource::ERC20Holders { backfilling, .. } => {
+                if *backfilling {
+                    Some(format!("{} holders (indexing)", self.chain.name))
+                } else {
+                    Some(format!("{} holders", self.chain.name))
+                }
+            }
+            _ => Some(self.chain.name.clone()),
+        }
     }
 
     pub fn logo(&self) -> Option<String> {
@@ -36,11 +127,21 @@ impl EvmMetadata {
                 symbol: _,
                 contract_address: _,
             } => None,
+            EvmSource::Safe { .. } => None,
+            EvmSource::EnsName { .. } => None,
+            EvmSource::Proxy { .. } => None,
+            EvmSource::Owner { .. } => None,
+            EvmSource::Storage { .. } => None,
+            EvmSource::Code { .. } => None,
+            EvmSource::ChainStatus { .. } => self.chain.icon.clone(),
+            EvmSource::TxCount { .. } => None,
+            EvmSource::ERC20Flow { .. } => None,
+            EvmSource::ERC20Holders { .. } => None,
         }
     }
 }
 
-#[derive(Serialize)]
+#[derive(Serialize, Deserialize)]
 #[serde(tag = "type")]
 pub enum EvmSource {
     NativeCurrency {
@@ -50,4 +151,64 @@ pub enum EvmSource {
         symbol: String,
         contract_address: EvmAddress,
     },
+    Safe {
+        safe_address: EvmAddress,
+        field: SafeField,
+        threshold: u64,
+        owners: Vec<EvmAddress>,
+        nonce: U256,
+    },
+    EnsName {
+        name: String,
+        expires_at: u64,
+    },
+    Proxy {
+        proxy_address: EvmAddress,
+        slot: ProxySlot,
+        target: EvmAddress,
+    },
+    Owner {
+        contract_address: EvmAddress,
+        owner: EvmAddress,
+    },
+    Storage {
+        contract_address: EvmAddress,
+        slot: U256,
+        kind: StorageKind,
+    },
+    Code {
+        address: EvmAddress,
+        kind: CodeKind,
+        size: usize,
+    },
+    ChainStatus {
+        metric: ChainMetric,
+    },
+    TxCount {
+        address: EvmAddress,
+        pending: bool,
+    },
+    ERC20Flow {
+        symbol: String,
+        contract_address: EvmAddress,
+        address: EvmAddress,
+        metric: FlowMetric,
+        window: BlockWindow,
+        from_block: u64,
+        to_block: u64,
+    },
+    ERC20Holders {
+        contract_address: EvmAddress,
+        indexed_block: u64,
+        /// Set while the index is catching up, when the count is partial.
+        #[serde(default)]
+        backfilling: bool,
+    },
+}
+
+#[derive(Serialize, Deserialize, Clone, Copy)]
+#[serde(rename_all = "snake_case")]
+pub enum CodeKind {
+    Size,
+    IsContract,
 }
@@ -0,0 +1,228 @@
+use std::{collections::HashMap, sync::Arc};
+
+use alloy::{
+    primitives::{address, Address, Bytes},
+    rpc::{
+        client::{ReqwestClient, Waiter},
+        types::{BlockId, BlockNumberOrTag, TransactionInput, TransactionRequest},
+    },
+    sol,
+    sol_types::SolCall,
+};
+use tokio::sync::RwLock;
+
+use crate::{error::BadgifyError, types::ChainID};
+
+// Multicall3 is deployed at the same address on every chain that has it.
+const MULTICALL3_ADDRESS: Address = address!("cA11bde05977b3631167028862bE2a173976CA11");
+
+sol! {
+    #[sol(rpc)]
+    contract Multicall3 {
+        struct Call3 {
+            address target;
+            bool allowFailure;
+            bytes callData;
+        }
+
+        struct Result {
+            bool success;
+            bytes returnData;
+        }
+
+        function aggregate3(Call3[] calldata calls) external payable returns (Result[] memory returnData);
+    }
+}
+
+/// A read-only contract call to execute as part of a multicall.
+#[derive(Debug, Clone)]
+pub struct ContractCall {
+    pub target: Address,
+    pub call_data: Bytes,
+}
+
+impl ContractCall {
+    pub fn new<C: SolCall>(target: Address, call: &C) -> Self {
+        Self {
+            target,
+            call_data: call.abi_encode().into(),
+        }
+    }
+}
+
+/// The return data of one call, or why it failed.
+pub type CallResult = Result<Bytes, BadgifyError>;
+
+/// Decodes the return data of a successful call.
+pub fn decode<C: SolCall>(result: &CallResult) -> Result<C::Return, BadgifyError> {
+    match result {
+        Ok(output) => Ok(C::abi_decode_returns(output, true)?),
+        Err(err) => Err(err.clone()),
+    }
+}
+
+/// Executes contract calls through Multicall3 `aggregate3` where it is
+/// deployed, falling back to a JSON-RPC batch and then to sequential calls.
+#[derive(Clone, Default)]
+pub struct Multicall {
+    deployments: Arc<RwLock<HashMap<ChainID, bool>>>,
+}
+
+impl Multicall {
+    pub async fn call(
+        &self,
+        chain_id: ChainID,
+        client: &ReqwestClient,
+        calls: &[ContractCall],
+        block: BlockId,
+    ) -> Result<Vec<CallResult>, BadgifyError> {
+        if self.is_deployed(chain_id, client).await {
+            match aggregate3(client, calls, block).await {
+                Ok(results) => return Ok(results),
+                Err(err) => println!("Multicall3 failed on chain {chain_id}: {err:?}"),
+            }
+        }
+
+        let results = match call_batch(client, calls, block)
+            .await
+            .map_err(|err| err.to_string())
+        {
+            Ok(results) => results,
+            Err(err) => {
+                println!("RPC batch failed on chain {chain_id}: {err}");
+                call_sequential(client, calls, block).await
+            }
+        };
+
+        // Per-call failures are only reported as such if the endpoint served
+        // something, or if every call was rejected by the EVM itself.
+        if results.iter().all(|result| result.is_err())
+            && results.iter().any(|result| {
+                result
+                    .as_ref()
+                    .is_err_and(BadgifyError::is_endpoint_failure)
+            })
+        {
+            return Err(BadgifyError::Upstream(format!(
+                "All calls failed: {results:?}"
+            )));
+        }
+
+        Ok(results)
+    }
+
+    async fn is_deployed(&self, chain_id: ChainID, client: &ReqwestClient) -> bool {
+        if let Some(deployed) = self.deployments.read().await.get(&chain_id) {
+            return *deployed;
+        }
+
+        // Only remember definite answers; a failing endpoint is retried next time.
+        match client
+            .request::<_, Bytes>(
+                "eth_getCode",
+                (MULTICALL3_ADDRESS, BlockNumberOrTag::Latest),
+            )
+            .await
+        {
+            Ok(code) => {
+                let deployed = !code.is_empty();
+                self.deployments.write().await.insert(chain_id, deployed);
+                deployed
+            }
+            Err(_) => false,
+        }
+    }
+}
+
+async fn aggregate3(
+    client: &ReqwestClient,
+    calls: &[ContractCall],
+    block: BlockId,
+) -> Result<Vec<CallResult>, BadgifyError> {
+    let aggregate_call = Multicall3::aggregate3Call::new((calls
+        .iter()
+        .map(|call| Multicall3::Call3 {
+            target: call.target,
+            allowFailure: true,
+            callData: call.call_data.clone(),
+        })
+        .collect(),));
+
+    let output: Bytes = client
+        .request(
+            "eth_call",
+            (
+                eth_call_request(MULTICALL3_ADDRESS, aggregate_call.abi_encode()),
+                block,
+            ),
+        )
+        .await?;
+
+    let results = Multicall3::aggregate3Call::abi_decode_returns(&output, true)?.returnData;
+
+    Ok(results
+        .into_iter()
+        .map(|result| match result.success {
+            true => Ok(result.returnData),
+            false => Err(BadgifyError::CallFailed(format!(
+                "reverted with {}",
+                result.returnData
+            ))),
+        })
+        .collect())
+}
+
+async fn call_batch(
+    client: &ReqwestClient,
+    calls: &[ContractCall],
+    block: BlockId,
+) -> Result<Vec<CallResult>, BadgifyError> {
+    let mut batch = client.new_batch();
+
+    let waiters = calls
+        .iter()
+        .map(|call| {
+            batch.add_call(
+                "eth_call",
+                &(eth_call_request(call.target, call.call_data.clone()), block),
+            )
+        })
+        .collect::<Result<Vec<Waiter<Bytes>>, _>>()?;
+
+    batch.send().await?;
+
+    let mut results = Vec::with_capacity(waiters.len());
+    for waiter in waiters {
+        results.push(waiter.await.map_err(BadgifyError::from));
+    }
+
+    Ok(results)
+}
+
+async fn call_sequential(
+    client: &ReqwestClient,
+    calls: &[ContractCall],
+    block: BlockId,
+) -> Vec<CallResult> {
+    let mut results = Vec::with_capacity(calls.len());
+
+    for call in calls {
+        let result = client
+            .request::<_, Bytes>(
+                "eth_call",
+                (eth_call_request(call.target, call.call_data.clone()), block),
+            )
+            .await
+            .map_err(BadgifyError::from);
+
+        results.push(result);
+    }
+
+    results
+}
+
+fn eth_call_request(to: Address, input: impl Into<Bytes>) -> TransactionRequest {
+    TransactionRequest::default()
+        .to(to)
+        .input(TransactionInput::from(input.into()))
+}
@@ -0,0 +1,129 @@
+use std::{error::Error, fmt::Display};
+
+use alloy::primitives::Address;
+
+use crate::error::BadgifyError;
+
+use super::SourceResponseWithMetadata;
+
+/// A value that independently queried endpoints can be checked to agree on.
+pub trait Verifiable {
+    fn agrees_with(&self, other: &Self) -> bool;
+    fn describe(&self) -> String;
+}
+
+impl Verifiable for SourceResponseWithMetadata {
+    fn agrees_with(&self, other: &Self) -> bool {
+        self.result == other.result
+    }
+
+    fn describe(&self) -> String {
+        format!("{:?}", self.result)
+    }
+}
+
+impl Verifiable for Address {
+    fn agrees_with(&self, other: &Self) -> bool {
+        self == other
+    }
+
+    fn describe(&self) -> String {
+        self.to_string()
+    }
+}
+
+/// Returned when fewer than the required number of endpoints agree on a
+/// value read at the same block.
+#[derive(Debug, Clone)]
+pub struct QuorumError {
+    pub block_number: u64,
+    pub required: usize,
+    /// Every distinct value returned, with the redacted URLs of the endpoints
+    /// that returned it.
+    pub responses: Vec<(String, Vec<String>)>,
+}
+
+impl Display for QuorumError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "RPC endpoints disagree at block {}, {} must agree:",
+            self.block_number, self.required
+        )?;
+
+        for (value, urls) in &self.responses {
+            write!(f, " {value} from {};", urls.join(", "))?;
+        }
+
+        Ok(())
+    }
+}
+
+impl Error for QuorumError {}
+
+/// Whether the outcome of a tally is settled with `pending` responses still
+/// outstanding: either `required` endpoints already agree, or no value can
+/// reach that many even if every pending response agrees with it.
+pub fn is_decided<T: Verifiable>(
+    responses: &[(String, T)],
+    required: usize,
+    pending: usize,
+) -> bool {
+    let most_agreeing = responses
+        .iter()
+        .map(|(_, value)| {
+            responses
+                .iter()
+                .filter(|(_, other)| other.agrees_with(value))
+                .count()
+        })
+        .max()
+        .unwrap_or(0);
+
+    most_agreeing >= required || most_agreeing + pending < required
+}
+
+/// Returns the value that at least `required` of `responses` agree on. If
+/// there is no such value but the responses do not conflict either, too few
+/// endpoints answered, and `failure`, the last error of an endpoint that did
+/// not, is returned instead of a [`QuorumError`].
+pub fn tally<T: Verifiable>(
+    responses: Vec<(String, T)>,
+    required: usize,
+    block_number: u64,
+    failure: Option<BadgifyError>,
+) -> Result<T, BadgifyError> {
+    let mut groups: Vec<(T, Vec<String>)> = Vec::new();
+
+    for (url, value) in responses {
+        match groups
+            .iter_mut()
+            .find(|(other, _)| other.agrees_with(&value))
+        {
+            Some((_, urls)) => urls.push(url),
+            None => groups.push((value, vec![url])),
+        }
+    }
+
+    if let Some(index) = groups.iter().position(|(_, urls)| urls.len() >= required) {
+        return Ok(groups.swap_remove(index).0);
+    }
+
+    if groups.len() < 2 {
+        return Err(failure.unwrap_or_else(|| {
+            BadgifyError::NoRpc(format!(
+                "Only {} of the {required} required endpoints answered",
+                groups.first().map_or(0, |(_, urls)| urls.len())
+            ))
+        }));
+    }
+
+    Err(BadgifyError::Quorum(QuorumError {
+        block_number,
+        required,
+        responses: groups
+            .into_iter()
+            .map(|(value, urls)| (value.describe(), urls))
+            .collect(),
+    }))
+}
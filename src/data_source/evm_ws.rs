@@ -0,0 +1,118 @@
+use std::{
+    collections::HashMap,
+    sync::{Arc, RwLock},
+    time::Duration,
+};
+
+use alloy::{
+    providers::{Provider, ProviderBuilder, RootProvider},
+    rpc::client::{ClientBuilder, RpcClient, WsConnect},
+};
+use tokio::sync::broadcast::error::RecvError;
+
+use crate::{error::BadgifyError, types::ChainID};
+
+use super::rpc_health::RpcHealth;
+
+const MIN_RECONNECT_DELAY: Duration = Duration::from_secs(1);
+const MAX_RECONNECT_DELAY: Duration = Duration::from_secs(60);
+// A connection that announces no block for this long is considered dead.
+const HEAD_TIMEOUT: Duration = Duration::from_secs(120);
+
+/// Persistent WebSocket connections to the chains configured to use them,
+/// and the latest block each of them has announced.
+#[derive(Default)]
+pub struct WsConnections {
+    clients: RwLock<HashMap<ChainID, (String, RpcClient)>>,
+    heads: RwLock<HashMap<ChainID, u64>>,
+}
+
+impl WsConnections {
+    /// Returns the open connection to `url`, if any.
+    pub fn client(&self, url: &str) -> Option<RpcClient> {
+        self.clients
+            .read()
+            .unwrap()
+            .values()
+            .find(|(connected_url, _)| connected_url == url)
+            .map(|(_, client)| client.clone())
+    }
+
+    /// Returns the URL of the open connection to `chain_id`, if any.
+    pub fn connected_url(&self, chain_id: ChainID) -> Option<String> {
+        self.clients
+            .read()
+            .unwrap()
+            .get(&chain_id)
+            .map(|(url, _)| url.clone())
+    }
+
+    /// Returns the number of the latest block announced for `chain_id`, which
+    /// changes exactly when the chain has a new head. Unknown while the
+    /// connection is down, since new heads may be missed meanwhile.
+    pub fn head(&self, chain_id: ChainID) -> Option<u64> {
+        self.heads.read().unwrap().get(&chain_id).copied()
+    }
+
+    /// Keeps a connection to one of `urls` open forever, moving on to the next
+    /// URL and backing off exponentially whenever a connection fails. Errors
+    /// are logged through `redact`, as they may repeat the URL.
+    pub async fn run(
+        self: Arc<Self>,
+        chain_id: ChainID,
+        urls: Vec<String>,
+        rpc_health: Arc<RpcHealth>,
+        redact: impl Fn(&str) -> String,
+    ) {
+        let mut reconnect_delay = MIN_RECONNECT_DELAY;
+
+        for url in urls.iter().cycle() {
+            match self.follow_heads(chain_id, url, &rpc_health).await {
+                Ok(()) => reconnect_delay = MIN_RECONNECT_DELAY,
+                Err(err) => {
+                    println!(
+                        "WebSocket connection to chain {chain_id} failed: {}",
+                        redact(&err.to_string())
+                    );
+                    rpc_health.record_failure(chain_id, url);
+                }
+            }
+
+            self.clients.write().unwrap().remove(&chain_id);
+            self.heads.write().unwrap().remove(&chain_id);
+
+            tokio::time::sleep(reconnect_delay).await;
+            reconnect_delay = (reconnect_delay * 2).min(MAX_RECONNECT_DELAY);
+        }
+    }
+
+    /// Follows the new heads of `chain_id` over `url` until the connection is
+    /// lost, which is only an error if it never worked.
+    async fn follow_heads(
+        &self,
+        chain_id: ChainID,
+        url: &str,
+        rpc_health: &RpcHealth,
+    ) -> Result<(), BadgifyError> {
+        let client = ClientBuilder::default().ws(WsConnect::new(url)).await?;
+        let provider: RootProvider = ProviderBuilder::default().on_client(client.clone());
+        let mut heads = provider.subscribe_blocks().await?;
+
+        self.clients
+            .write()
+            .unwrap()
+            .insert(chain_id, (url.to_string(), client));
+
+        loop {
+            let header = match tokio::time::timeout(HEAD_TIMEOUT, heads.recv()).await {
+                Ok(Ok(header)) => header,
+                Ok(Err(RecvError::Lagged(_))) => continue,
+                Ok(Err(RecvError::Closed)) => return Ok(()),
+                Err(_) => return Err(BadgifyError::Upstream("No new heads received".to_string())),
+            };
+
+            self.heads.write().unwrap().insert(chain_id, header.number);
+            rpc_health.record_success(chain_id, url, None, Some(header.number));
+        }
+    }
+}
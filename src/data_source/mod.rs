@@ -1,35 +1,85 @@
 mod bitcoin;
 mod bitcoing_metadata;
+pub mod chains;
 mod evm;
+pub mod evm_hedging;
+pub mod evm_indexer;
+pub mod evm_logs;
 mod evm_metadata;
+pub mod evm_multicall;
+pub mod evm_quorum;
+mod evm_ws;
+pub mod query_cache;
+pub mod rpc_endpoints;
+pub mod rpc_health;
+pub mod stale_store;
 
-use alloy::primitives::U256;
+use alloy::primitives::{I256, U256};
 use bitcoing_metadata::BitcoinMetadata;
+use chains::ChainInfo;
 use evm_metadata::EvmMetadata;
+use query_cache::QueryCache;
+use stale_store::StaleStore;
 
-use crate::query::Query;
-use serde::{ser::SerializeMap, Serialize};
-use std::error::Error;
+use crate::{
+    config::Config,
+    error::BadgifyError,
+    evm_chain_icons::ChainIcons,
+    query::{
+        bitcoin::BitcoinNetwork,
+        evm::{ChainMetric, EvmQuery},
+        Query,
+    },
+    types::{ChainID, EvmAddress},
+    utils::{format_duration, format_relative_time, group_thousands, shorten_address, unix_now},
+};
+use rpc_health::EndpointReport;
+use serde::{ser::SerializeMap, Deserialize, Serialize};
+use std::{collections::HashMap, error::Error, sync::Arc, time::Duration};
 
-#[derive(Debug)]
+// Stale results are replaced as soon as upstreams recover, so clients should
+// not keep them for long.
+const STALE_RESULT_MAX_AGE: Duration = Duration::from_secs(60);
+
+#[derive(Debug, PartialEq)]
 pub enum SourceResponse {
     Decimal { value: U256, decimals: u8 },
+    Integer { value: U256 },
+    SignedDecimal { value: I256, decimals: u8 },
     AlphaNumeric { value: String },
+    Timestamp { value: u64 },
+    Duration { seconds: u64 },
+    Address { value: EvmAddress },
+    Boolean { value: bool },
 }
 
-#[derive(Serialize)]
+#[derive(Serialize, Deserialize)]
 pub struct SourceResponseWithMetadata {
     pub result: SourceResponse,
     pub metadata: SourceMetadata,
 }
 
+/// A query result, possibly an earlier one served because upstreams failed.
+#[derive(Serialize)]
+pub struct QueryResult {
+    /// How long clients may cache the result for.
+    #[serde(skip)]
+    pub max_age: Duration,
+    #[serde(flatten)]
+    pub data: Arc<SourceResponseWithMetadata>,
+    pub stale: bool,
+    /// Unix time the result was read at, set for stale results.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub fetched_at: Option<u64>,
+}
+
 impl SourceResponseWithMetadata {
     fn new(result: SourceResponse, metadata: SourceMetadata) -> Self {
         Self { result, metadata }
     }
 }
 
-#[derive(Serialize)]
+#[derive(Serialize, Deserialize)]
 #[serde(tag = "type")]
 pub enum SourceMetadata {
     Bitcoin(BitcoinMetadata),
@@ -37,7 +87,7 @@ pub enum SourceMetadata {
 }
 
 impl SourceMetadata {
-    pub fn symbol(&self) -> String {
+    pub fn symbol(&self) -> Option<String> {
         match self {
             Self::Evm(evm_metadata) => evm_metadata.symbol(),
             Self::Bitcoin(bitcoing_metadata) => bitcoing_metadata.symbol(),
@@ -57,6 +107,13 @@ impl SourceMetadata {
             Self::Bitcoin(bitcoing_metadata) => bitcoing_metadata.logo(),
         }
     }
+
+    pub fn is_testnet(&self) -> bool {
+        match self {
+            Self::Evm(evm_metadata) => evm_metadata.testnet,
+            Self::Bitcoin(bitcoing_metadata) => bitcoing_metadata.is_testnet(),
+        }
+    }
 }
 
 impl Serialize for SourceResponse {
@@ -80,13 +137,106 @@ impl Serialize for SourceResponse {
                 )?;
                 map.end()
             }
+            Self::SignedDecimal { value, decimals } => {
+                let formatted = &alloy::primitives::utils::format_units(*value, *decimals)
+                    .map_err(|_| serde::ser::Error::custom("Cannot format decimal units"))?;
+
+                let mut map = serializer.serialize_map(Some(5))?;
+                map.serialize_entry("type", "signed_decimal")?;
+                map.serialize_entry("value", &value.to_string())?;
+                map.serialize_entry("decimals", &decimals)?;
+                map.serialize_entry("formatted", formatted)?;
+                map.serialize_entry("formatted_tiny", &self.formatted_tiny())?;
+                map.end()
+            }
+            Self::Integer { value } => {
+                let mut map = serializer.serialize_map(Some(3))?;
+                map.serialize_entry("type", "integer")?;
+                map.serialize_entry("value", &value.to_string())?;
+                map.serialize_entry("formatted_tiny", &self.formatted_tiny())?;
+                map.end()
+            }
             Self::AlphaNumeric { value } => {
                 let mut map = serializer.serialize_map(Some(2))?;
                 map.serialize_entry("type", "alphanumeric")?;
                 map.serialize_entry("value", &value)?;
                 map.end()
             }
+            Self::Address { value } => {
+                let mut map = serializer.serialize_map(Some(3))?;
+                map.serialize_entry("type", "address")?;
+                map.serialize_entry("value", &value.to_checksum(None))?;
+                map.serialize_entry("formatted_tiny", &self.formatted_tiny())?;
+                map.end()
+            }
+            Self::Boolean { value } => {
+                let mut map = serializer.serialize_map(Some(3))?;
+                map.serialize_entry("type", "boolean")?;
+                map.serialize_entry("value", &value)?;
+                map.serialize_entry("formatted_tiny", &self.formatted_tiny())?;
+                map.end()
+            }
+            Self::Duration { seconds } => {
+                let mut map = serializer.serialize_map(Some(3))?;
+                map.serialize_entry("type", "duration")?;
+                map.serialize_entry("seconds", &seconds)?;
+                map.serialize_entry("formatted_tiny", &self.formatted_tiny())?;
+                map.end()
+            }
+            Self::Timestamp { value } => {
+                let mut map = serializer.serialize_map(Some(3))?;
+                map.serialize_entry("type", "timestamp")?;
+                map.serialize_entry("value", &value)?;
+                map.serialize_entry("formatted_tiny", &self.formatted_tiny())?;
+                map.end()
+            }
+        }
+    }
+}
+
+/// Reads back what `Serialize` writes, ignoring the derived `formatted` fields.
+impl<'de> Deserialize<'de> for SourceResponse {
+    fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
+    where
+        D: serde::Deserializer<'de>,
+    {
+        #[derive(Deserialize)]
+        #[serde(tag = "type", rename_all = "snake_case")]
+        enum Serialized {
+            Decimal { value: String, decimals: u8 },
+            SignedDecimal { value: String, decimals: u8 },
+            Integer { value: String },
+            Alphanumeric { value: String },
+            Address { value: EvmAddress },
+            Boolean { value: bool },
+            Duration { seconds: u64 },
+            Timestamp { value: u64 },
+        }
+
+        fn parse<T: std::str::FromStr, E: serde::de::Error>(value: &str) -> Result<T, E> {
+            value
+                .parse()
+                .map_err(|_| E::custom(format!("invalid number {value}")))
         }
+
+        Ok(match Serialized::deserialize(deserializer)? {
+            Serialized::Decimal { value, decimals } => Self::Decimal {
+                value: parse(&value)?,
+                decimals,
+            },
+            Serialized::SignedDecimal { value, decimals } => Self::SignedDecimal {
+                value: parse(&value)?,
+                decimals,
+            },
+            Serialized::Integer { value } => Self::Integer {
+                value: parse(&value)?,
+            },
+            Serialized::Alphanumeric { value } => Self::AlphaNumeric { value },
+            Serialized::Address { value } => Self::Address { value },
+            Serialized::Boolean { value } => Self::Boolean { value },
+            Serialized::Duration { seconds } => Self::Duration { seconds },
+            Serialized::Timestamp { value } => Self::Timestamp { value },
+        })
     }
 }
 
@@ -104,7 +254,26 @@ impl SourceResponse {
 
                 to_tiny(value, &formatted).unwrap_or("-".to_string())
             }
+            Self::SignedDecimal { value, decimals } => {
+                let (sign, abs) = value.into_sign_and_abs();
+                let abs = Self::Decimal {
+                    value: abs,
+                    decimals: *decimals,
+                }
+                .formatted_tiny();
+
+                match abs.as_str() {
+                    "0" => abs,
+                    _ if sign.is_negative() => format!("-{abs}"),
+                    _ => format!("+{abs}"),
+                }
+            }
+            Self::Integer { value } => group_thousands(&value.to_string()),
             Self::AlphaNumeric { value } => value.to_string(),
+            Self::Timestamp { value } => format_relative_time(*value, unix_now()),
+            Self::Duration { seconds } => format_duration(*seconds),
+            Self::Address { value } => shorten_address(value),
+            Self::Boolean { value } => if *value { "yes" } else { "no" }.to_string(),
         }
     }
 }
@@ -116,7 +285,7 @@ fn to_tiny(value: &U256, formatted: &str) -> Result<String, String> {
         let partial: String = partial.chars().take(4).collect::<String>();
 
         match partial.trim_end_matches("0") {
-            s if s.is_empty() && full == "0" && value.to::<u32>() != 0 => Ok("~0".to_string()),
+            s if s.is_empty() && full == "0" && !value.is_zero() => Ok("~0".to_string()),
             "" => Ok(full.to_string()),
             s => {
                 let mut res = full.to_string();
@@ -131,24 +300,160 @@ fn to_tiny(value: &U256, formatted: &str) -> Result<String, String> {
     }
 }
 
-#[derive(Default)]
 pub struct DataSource {
-    evm_data_source: evm::EvmDataSource,
+    evm_data_source: Arc<evm::EvmDataSource>,
     bitcoin_data_source: bitcoin::BitcoinDataSource,
+    chain_icons: ChainIcons,
+    cache: QueryCache<SourceResponseWithMetadata>,
+    stale: StaleStore<SourceResponseWithMetadata>,
 }
 
 impl DataSource {
-    pub async fn get_data(
-        &self,
-        query: Query,
-    ) -> Result<SourceResponseWithMetadata, Box<dyn Error>> {
+    /// Builds the data sources and starts any configured background indexing.
+    pub fn new(config: &Config) -> Result<Self, Box<dyn Error>> {
+        let evm_data_source = Arc::new(evm::EvmDataSource::new(config)?);
+
+        if let Some(holder_index) = evm_data_source.holder_index() {
+            actix_web::rt::spawn(holder_index.run(evm_data_source.clone()));
+        }
+
+        actix_web::rt::spawn(evm_data_source.chain_list().run());
+        actix_web::rt::spawn(evm_data_source.clone().run_health_probes());
+
+        for chain_id in evm_data_source.websocket_chains() {
+            actix_web::rt::spawn(evm_data_source.clone().run_websocket(chain_id));
+        }
+
+        Ok(Self {
+            evm_data_source,
+            bitcoin_data_source: bitcoin::BitcoinDataSource,
+            chain_icons: ChainIcons::new(&config.chain_list),
+            cache: QueryCache::new(&config.cache),
+            stale: StaleStore::open(&config.cache)?,
+        })
+    }
+
+    /// Answers `query` from the cache if possible. Identical queries arriving
+    /// while one is being fetched wait for its result. If upstreams fail, the
+    /// last successful result is served instead, marked as stale.
+    pub async fn get_data(&self, query: Query) -> Result<QueryResult, BadgifyError> {
+        let key = query.cache_key();
+        let stale_key = query.storage_key();
+        let kind = query.kind();
+        let head = self.cache_head(&query);
+        let ttl = self.cache.ttl(kind);
+
+        let result = self
+            .cache
+            .get_or_fetch(key.clone(), kind, head, || async {
+                let result = match query {
+                    Query::Evm(evm_query) => self.evm_data_source.get_data(evm_query).await,
+                    Query::Bitcoin(bitcoin_query) => {
+                        self.bitcoin_data_source.get_data(bitcoin_query).await
+                    }
+                };
+
+                let result = Arc::new(result?);
+                self.stale.record(&stale_key, result.clone(), unix_now());
+
+                Ok(result)
+            })
+            .await;
+
+        match result {
+            Ok((data, max_age)) => Ok(QueryResult {
+                max_age,
+                data,
+                stale: false,
+                fetched_at: None,
+            }),
+            Err(err) if err.is_endpoint_failure() => {
+                match self.stale.get(&stale_key, unix_now()).await {
+                    Some(stale) => {
+                        println!("Serving stale result of {stale_key} after error: {err}");
+                        Ok(QueryResult {
+                            max_age: ttl.min(STALE_RESULT_MAX_AGE),
+                            data: stale.result,
+                            stale: true,
+                            fetched_at: Some(stale.fetched_at),
+                        })
+                    }
+                    None => Err(err),
+                }
+            }
+            Err(err) => Err(err),
+        }
+    }
+
+    /// How long results of `query` are cached for.
+    pub fn cache_ttl(&self, query: &Query) -> Duration {
+        self.cache.ttl(query.kind())
+    }
+
+    /// Head that results of `query` are pinned to in the cache. Block age
+    /// and pending transactions change between blocks, so they only expire.
+    fn cache_head(&self, query: &Query) -> Option<u64> {
         match query {
-            Query::Evm(evm_query) => self.evm_data_source.get_data(evm_query).await,
-            Query::Bitcoin(bitcoin_query) => self.bitcoin_data_source.get_data(bitcoin_query).await,
+            Query::Evm(EvmQuery::ChainStatus {
+                metric: ChainMetric::BlockAge,
+                ..
+            })
+            | Query::Evm(EvmQuery::TxCount { pending: true, .. }) => None,
+            Query::Evm(evm_query) => self.chain_head(evm_query.chain_id()),
+            Query::Bitcoin(_) => None,
         }
     }
 
-    pub async fn get_scanner_link(&self, query: Query) -> Result<String, Box<dyn Error>> {
+    /// Returns the latest block of `chain_id` for chains followed over
+    /// WebSocket. Results read from such a chain stay current until it changes.
+    pub fn chain_head(&self, chain_id: ChainID) -> Option<u64> {
+        self.evm_data_source.chain_head(chain_id)
+    }
+
+    /// Returns every supported EVM chain, followed by the Bitcoin networks.
+    pub fn chains(&self) -> Vec<ChainInfo> {
+        let chain_list = self.evm_data_source.chain_list();
+
+        chain_list
+            .chains()
+            .into_iter()
+            .map(|chain| self.evm_data_source.chain_info(chain))
+            .chain(BitcoinNetwork::ALL.into_iter().map(ChainInfo::bitcoin))
+            .collect()
+    }
+
+    /// Looks up a chain by EVM chain ID, EVM short name or `btc-<network>`.
+    pub fn chain(&self, id: &str) -> Option<ChainInfo> {
+        if let Some(bitcoin) = BitcoinNetwork::ALL
+            .into_iter()
+            .map(ChainInfo::bitcoin)
+            .find(|info| info.id.eq_ignore_ascii_case(id))
+        {
+            return Some(bitcoin);
+        }
+
+        let chain_list = self.evm_data_source.chain_list();
+        let chain = match id.parse::<ChainID>() {
+            Ok(chain_id) => chain_list.get_chain(chain_id),
+            Err(_) => chain_list.get_chain_by_short_name(id),
+        }?;
+
+        Some(self.evm_data_source.chain_info(chain))
+    }
+
+    /// Returns the chain icon shown with `metadata` as an embeddable SVG.
+    pub async fn logo_svg(&self, metadata: &SourceMetadata) -> Option<Arc<String>> {
+        match metadata {
+            SourceMetadata::Evm(evm_metadata) => self.chain_icons.svg(&evm_metadata.logo()?).await,
+            SourceMetadata::Bitcoin(_) => None,
+        }
+    }
+
+    pub fn rpc_health_report(&self) -> HashMap<String, Vec<EndpointReport>> {
+        self.evm_data_source.rpc_health_report()
+    }
+
+    pub async fn get_scanner_link(&self, query: Query) -> Result<String, BadgifyError> {
         match query {
             Query::Evm(evm_query) => self.evm_data_source.get_scanner_link(evm_query).await,
             Query::Bitcoin(bitcoin_query) => {
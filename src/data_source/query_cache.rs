@@ -0,0 +1,170 @@
+use std::{
+    collections::HashMap,
+    future::Future,
+    num::NonZeroUsize,
+    sync::{Arc, Mutex},
+    time::{Duration, Instant},
+};
+
+use lru::LruCache;
+use tokio::sync::OnceCell;
+
+use crate::{config::CacheConfig, error::BadgifyError};
+
+pub type CachedResult<T> = Result<Arc<T>, BadgifyError>;
+
+/// A result and how much longer it stays cached.
+pub type Cached<T> = Result<(Arc<T>, Duration), BadgifyError>;
+
+/// A fetch shared by identical queries, with when it finished.
+type SharedFetch<T> = Arc<OnceCell<(Instant, CachedResult<T>)>>;
+
+/// Caches query results for a time that depends on the query type, and
+/// coalesces concurrent identical queries into a single upstream request.
+pub struct QueryCache<T> {
+    ttl_overrides: HashMap<String, Duration>,
+    entries: Mutex<LruCache<String, CacheEntry<T>>>,
+    in_flight: Mutex<HashMap<String, SharedFetch<T>>>,
+}
+
+struct CacheEntry<T> {
+    result: Arc<T>,
+    fetched_at: Instant,
+    ttl: Duration,
+    /// Head of the chain when the result was read, if the chain is followed.
+    head: Option<u64>,
+}
+
+impl<T> QueryCache<T> {
+    pub fn new(config: &CacheConfig) -> Self {
+        let max_entries = NonZeroUsize::new(config.max_entries).unwrap_or(NonZeroUsize::MIN);
+
+        Self {
+            ttl_overrides: config
+                .ttl_secs
+                .iter()
+                .map(|(kind, secs)| (kind.clone(), Duration::from_secs(*secs)))
+                .collect(),
+            entries: Mutex::new(LruCache::new(max_entries)),
+            in_flight: Mutex::new(HashMap::new()),
+        }
+    }
+
+    /// How long results of queries of `kind` are cached for.
+    pub fn ttl(&self, kind: &str) -> Duration {
+        self.ttl_overrides
+            .get(kind)
+            .copied()
+            .unwrap_or_else(|| default_ttl(kind))
+    }
+
+    /// Returns the cached result for `key`, or runs `fetch` to get it, with
+    /// what is left of its TTL. Results read while the chain was at `head`
+    /// stay valid until the head changes; others until their TTL runs out.
+    /// Errors are never cached, but are shared with the requests that waited
+    /// for them.
+    pub async fn get_or_fetch<F, Fut>(
+        &self,
+        key: String,
+        kind: &str,
+        head: Option<u64>,
+        fetch: F,
+    ) -> Cached<T>
+    where
+        F: FnOnce() -> Fut,
+        Fut: Future<Output = CachedResult<T>>,
+    {
+        let ttl = self.ttl(kind);
+        if ttl.is_zero() {
+            return fetch().await.map(|result| (result, Duration::ZERO));
+        }
+
+        if let Some(cached) = self.lookup(&key, head) {
+            return Ok(cached);
+        }
+
+        let cell = self
+            .in_flight
+            .lock()
+            .unwrap()
+            .entry(key.clone())
+            .or_default()
+            .clone();
+
+        let result = cell
+            .get_or_init(|| async {
+                let result = fetch().await;
+
+                if let Ok(result) = &result {
+                    self.entries.lock().unwrap().put(
+                        key.clone(),
+                        CacheEntry {
+                            result: result.clone(),
+                            fetched_at: Instant::now(),
+                            ttl,
+                            head,
+                        },
+                    );
+                }
+
+                (Instant::now(), result)
+            })
+            .await
+            .clone();
+
+        let mut in_flight = self.in_flight.lock().unwrap();
+        if in_flight
+            .get(&key)
+            .is_some_and(|current| Arc::ptr_eq(current, &cell))
+        {
+            in_flight.remove(&key);
+        }
+
+        // Requests that waited for the fetch get what is left of its TTL.
+        let (fetched_at, result) = result;
+        result.map(|result| (result, ttl.saturating_sub(fetched_at.elapsed())))
+    }
+
+    /// Number of cached results, including expired ones not yet evicted.
+    pub fn len(&self) -> usize {
+        self.entries.lock().unwrap().len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    fn lookup(&self, key: &str, head: Option<u64>) -> Option<(Arc<T>, Duration)> {
+        let mut entries = self.entries.lock().unwrap();
+        let entry = entries.get(key)?;
+
+        let is_fresh = match (entry.head, head) {
+            (Some(cached_head), Some(head)) => cached_head == head,
+            _ => entry.fetched_at.elapsed() < entry.ttl,
+        };
+
+        if !is_fresh {
+            entries.pop(key);
+            return None;
+        }
+
+        // Head-pinned results may outlive their TTL, but clients cannot know
+        // when the head changes, so they keep them no longer than the TTL.
+        let remaining = entry.ttl.saturating_sub(entry.fetched_at.elapsed());
+
+        Some((entry.result.clone(), remaining))
+    }
+}
+
+/// Results that change with every block are cached briefly, slowly changing
+/// configuration like owners or ENS expiry for longer.
+fn default_ttl(kind: &str) -> Duration {
+    let secs = match kind {
+        "block_number" | "block_age" | "gas_price" | "base_fee" | "priority_fee" => 5,
+        "proxy" | "owner" | "erc20_flow" | "safe" => 5 * 60,
+        "ens_expiry" | "code_size" | "is_contract" => 60 * 60,
+        _ => 60,
+    };
+
+    Duration::from_secs(secs)
+}
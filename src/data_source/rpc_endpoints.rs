@@ -0,0 +1,121 @@
+use std::{collections::HashMap, error::Error, path::Path};
+
+use crate::{config::RpcConfig, types::ChainID};
+
+/// Operator-configured RPC endpoints with their placeholders filled in.
+#[derive(Default)]
+pub struct RpcEndpoints {
+    chains: HashMap<ChainID, ChainEndpoints>,
+    /// Filled in secrets and the placeholders they came from, for redaction.
+    secrets: Vec<(String, String)>,
+}
+
+impl RpcEndpoints {
+    /// Resolves placeholders from the environment, falling back to files in
+    /// the configured secrets directory.
+    pub fn from_config(config: &RpcConfig) -> Result<Self, Box<dyn Error>> {
+        Self::resolve(config, |name| {
+            std::env::var(name)
+                .ok()
+                .or_else(|| read_secret_file(&config.secrets_dir, name))
+        })
+    }
+
+    pub fn resolve(
+        config: &RpcConfig,
+        lookup: impl Fn(&str) -> Option<String>,
+    ) -> Result<Self, Box<dyn Error>> {
+        let mut endpoints = Self::default();
+
+        for chain in &config.endpoints {
+            let mut urls = Vec::with_capacity(chain.urls.len());
+
+            for template in &chain.urls {
+                urls.push(endpoints.expand(template, &lookup)?);
+            }
+
+            endpoints.chains.insert(
+                ChainID::from(chain.chain_id),
+                ChainEndpoints {
+                    urls,
+                    use_chainlist: chain.use_chainlist,
+                    websocket: chain.websocket,
+                },
+            );
+        }
+
+        Ok(endpoints)
+    }
+
+    pub fn urls(&self, chain_id: ChainID) -> &[String] {
+        self.chains
+            .get(&chain_id)
+            .map(|chain| chain.urls.as_slice())
+            .unwrap_or_default()
+    }
+
+    pub fn use_chainlist(&self, chain_id: ChainID) -> bool {
+        self.chains
+            .get(&chain_id)
+            .is_none_or(|chain| chain.use_chainlist)
+    }
+
+    /// Returns the chains configured to be followed over WebSocket.
+    pub fn websocket_chains(&self) -> Vec<ChainID> {
+        self.chains
+            .iter()
+            .filter(|(_, chain)| chain.websocket)
+            .map(|(chain_id, _)| *chain_id)
+            .collect()
+    }
+
+    /// Puts the placeholders back into `url` so it can be logged or shown.
+    pub fn redact(&self, url: &str) -> String {
+        self.secrets
+            .iter()
+            .fold(url.to_string(), |url, (secret, name)| {
+                url.replace(secret, &format!("${{{name}}}"))
+            })
+    }
+
+    fn expand(
+        &mut self,
+        template: &str,
+        lookup: &impl Fn(&str) -> Option<String>,
+    ) -> Result<String, Box<dyn Error>> {
+        let mut url = String::with_capacity(template.len());
+        let mut rest = template;
+
+        while let Some(start) = rest.find("${") {
+            let end = rest[start..]
+                .find('}')
+                .ok_or(format!("Unclosed placeholder in RPC URL {template}"))?;
+            let name = &rest[start + 2..start + end];
+
+            let secret = lookup(name)
+                .filter(|secret| !secret.is_empty())
+                .ok_or(format!("RPC URL placeholder ${{{name}}} is not set"))?;
+
+            url.push_str(&rest[..start]);
+            url.push_str(&secret);
+            self.secrets.push((secret, name.to_string()));
+
+            rest = &rest[start + end + 1..];
+        }
+
+        url.push_str(rest);
+        Ok(url)
+    }
+}
+
+struct ChainEndpoints {
+    urls: Vec<String>,
+    use_chainlist: bool,
+    websocket: bool,
+}
+
+fn read_secret_file(secrets_dir: &Path, name: &str) -> Option<String> {
+    std::fs::read_to_string(secrets_dir.join(name))
+        .ok()
+        .map(|secret| secret.trim().to_string())
+}
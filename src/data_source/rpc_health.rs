@@ -0,0 +1,241 @@
+use std::{
+    collections::HashMap,
+    sync::Mutex,
+    time::{Duration, Instant},
+};
+
+use serde::Serialize;
+
+use crate::{config::RpcConfig, types::ChainID};
+
+// Weight of the newest sample in the moving averages.
+const EWMA_WEIGHT: f64 = 0.2;
+// Assumed latency of endpoints that have not been measured yet, so they are
+// still tried before slow ones.
+const UNKNOWN_LATENCY_MS: f64 = 500.0;
+// How much a fully failing endpoint is penalised relative to its latency.
+const ERROR_RATE_PENALTY: f64 = 4.0;
+const BLOCK_LAG_PENALTY_MS: f64 = 100.0;
+
+/// Tracks latency, error rate and block height of every RPC endpoint used,
+/// ejecting endpoints that keep failing for a cool-down period.
+pub struct RpcHealth {
+    failure_threshold: u32,
+    cooldown: Duration,
+    max_block_lag: u64,
+    endpoints: Mutex<HashMap<ChainID, HashMap<String, EndpointHealth>>>,
+}
+
+#[derive(Default)]
+struct EndpointHealth {
+    latency_ms: Option<f64>,
+    error_rate: f64,
+    requests: u64,
+    failures: u64,
+    consecutive_failures: u32,
+    block_number: Option<u64>,
+    ejected_until: Option<Instant>,
+}
+
+/// Health of one endpoint as reported by the admin API.
+#[derive(Debug, Serialize)]
+pub struct EndpointReport {
+    pub url: String,
+    pub score: f64,
+    pub latency_ms: Option<f64>,
+    pub error_rate: f64,
+    pub requests: u64,
+    pub failures: u64,
+    pub block_number: Option<u64>,
+    pub block_lag: Option<u64>,
+    pub ejected_for_secs: Option<u64>,
+}
+
+impl RpcHealth {
+    pub fn new(config: &RpcConfig) -> Self {
+        Self {
+            failure_threshold: config.failure_threshold.max(1),
+            cooldown: Duration::from_secs(config.cooldown_secs),
+            max_block_lag: config.max_block_lag,
+            endpoints: Mutex::new(HashMap::new()),
+        }
+    }
+
+    /// Records a successful request and how long it took, if timed.
+    /// `block_number` is only given by health probes and WebSocket heads,
+    /// which read the latest block.
+    pub fn record_success(
+        &self,
+        chain_id: ChainID,
+        url: &str,
+        latency: Option<Duration>,
+        block_number: Option<u64>,
+    ) {
+        let mut endpoints = self.endpoints.lock().unwrap();
+        let endpoint = endpoint_entry(&mut endpoints, chain_id, url);
+
+        endpoint.requests += 1;
+        endpoint.consecutive_failures = 0;
+        endpoint.ejected_until = None;
+        endpoint.error_rate *= 1.0 - EWMA_WEIGHT;
+
+        if let Some(latency) = latency {
+            let latency_ms = latency.as_secs_f64() * 1000.0;
+            endpoint.latency_ms = Some(match endpoint.latency_ms {
+                Some(average) => average + EWMA_WEIGHT * (latency_ms - average),
+                None => latency_ms,
+            });
+        }
+
+        if block_number.is_some() {
+            endpoint.block_number = block_number;
+        }
+    }
+
+    /// Records a failed request, ejecting the endpoint once it has failed
+    /// `failure_threshold` times in a row. An endpoint that fails again right
+    /// after its cool-down is ejected again.
+    pub fn record_failure(&self, chain_id: ChainID, url: &str) {
+        let mut endpoints = self.endpoints.lock().unwrap();
+        let endpoint = endpoint_entry(&mut endpoints, chain_id, url);
+
+        endpoint.requests += 1;
+        endpoint.failures += 1;
+        endpoint.consecutive_failures += 1;
+        endpoint.error_rate += EWMA_WEIGHT * (1.0 - endpoint.error_rate);
+
+        if endpoint.consecutive_failures >= self.failure_threshold {
+            endpoint.ejected_until = Some(Instant::now() + self.cooldown);
+        }
+    }
+
+    /// Returns whether `url` is not currently ejected.
+    pub fn is_available(&self, chain_id: ChainID, url: &str) -> bool {
+        let now = Instant::now();
+
+        self.endpoints
+            .lock()
+            .unwrap()
+            .get(&chain_id)
+            .and_then(|endpoints| endpoints.get(url))
+            .is_none_or(|endpoint| !endpoint.is_ejected(now))
+    }
+
+    /// Orders `urls` by preference: endpoints that are not ejected and not
+    /// lagging behind come first, fastest and most reliable first. Ejected
+    /// endpoints are left out unless every endpoint is ejected. The order of
+    /// equally scored endpoints is kept, so callers can shuffle beforehand.
+    pub fn rank(&self, chain_id: ChainID, urls: Vec<String>) -> Vec<String> {
+        let now = Instant::now();
+        let endpoints = self.endpoints.lock().unwrap();
+        let Some(endpoints) = endpoints.get(&chain_id) else {
+            return urls;
+        };
+
+        let head = chain_head(endpoints);
+        let (ejected, mut available): (Vec<_>, Vec<_>) = urls
+            .into_iter()
+            .partition(|url| endpoints.get(url).is_some_and(|e| e.is_ejected(now)));
+
+        if available.is_empty() {
+            // Better to retry the endpoint that comes back first than to fail.
+            let mut ejected = ejected;
+            ejected.sort_by_key(|url| endpoints.get(url).and_then(|e| e.ejected_until));
+            return ejected;
+        }
+
+        available.sort_by(|a, b| {
+            let a = endpoints.get(a);
+            let b = endpoints.get(b);
+
+            let a_lagging = a.is_some_and(|e| e.block_lag(head) > self.max_block_lag);
+            let b_lagging = b.is_some_and(|e| e.block_lag(head) > self.max_block_lag);
+
+            a_lagging
+                .cmp(&b_lagging)
+                .then_with(|| score(a, head).total_cmp(&score(b, head)))
+        });
+
+        available
+    }
+
+    /// Returns the chains that have been used so far.
+    pub fn tracked_chains(&self) -> Vec<ChainID> {
+        self.endpoints.lock().unwrap().keys().copied().collect()
+    }
+
+    /// Reports the health of every known endpoint, grouped by chain and
+    /// ordered from best to worst score.
+    pub fn report(&self) -> HashMap<String, Vec<EndpointReport>> {
+        let now = Instant::now();
+        let endpoints = self.endpoints.lock().unwrap();
+
+        endpoints
+            .iter()
+            .map(|(chain_id, endpoints)| {
+                let head = chain_head(endpoints);
+                let mut reports: Vec<_> = endpoints
+                    .iter()
+                    .map(|(url, endpoint)| EndpointReport {
+                        url: url.clone(),
+                        score: score(Some(endpoint), head),
+                        latency_ms: endpoint.latency_ms,
+                        error_rate: endpoint.error_rate,
+                        requests: endpoint.requests,
+                        failures: endpoint.failures,
+                        block_number: endpoint.block_number,
+                        block_lag: endpoint.block_number.map(|_| endpoint.block_lag(head)),
+                        ejected_for_secs: endpoint
+                            .ejected_until
+                            .filter(|until| *until > now)
+                            .map(|until| (until - now).as_secs()),
+                    })
+                    .collect();
+
+                reports.sort_by(|a, b| a.score.total_cmp(&b.score));
+
+                (chain_id.to_string(), reports)
+            })
+            .collect()
+    }
+}
+
+impl EndpointHealth {
+    fn is_ejected(&self, now: Instant) -> bool {
+        self.ejected_until.is_some_and(|until| until > now)
+    }
+
+    fn block_lag(&self, head: Option<u64>) -> u64 {
+        match (self.block_number, head) {
+            (Some(block_number), Some(head)) => head.saturating_sub(block_number),
+            _ => 0,
+        }
+    }
+}
+
+fn endpoint_entry<'a>(
+    endpoints: &'a mut HashMap<ChainID, HashMap<String, EndpointHealth>>,
+    chain_id: ChainID,
+    url: &str,
+) -> &'a mut EndpointHealth {
+    endpoints
+        .entry(chain_id)
+        .or_default()
+        .entry(url.to_string())
+        .or_default()
+}
+
+fn chain_head(endpoints: &HashMap<String, EndpointHealth>) -> Option<u64> {
+    endpoints.values().filter_map(|e| e.block_number).max()
+}
+
+/// Expected cost of using an endpoint in milliseconds; lower is better.
+fn score(endpoint: Option<&EndpointHealth>, head: Option<u64>) -> f64 {
+    let Some(endpoint) = endpoint else {
+        return UNKNOWN_LATENCY_MS;
+    };
+
+    endpoint.latency_ms.unwrap_or(UNKNOWN_LATENCY_MS)
+        * (1.0 + ERROR_RATE_PENALTY * endpoint.error_rate)
+        + endpoint.block_lag(head) as f64 * BLOCK_LAG_PENALTY_MS
+}
@@ -0,0 +1,216 @@
+use std::{
+    num::NonZeroUsize,
+    path::Path,
+    sync::{mpsc, Arc, Mutex},
+    thread::JoinHandle,
+};
+
+use lru::LruCache;
+use rusqlite::{params, Connection, OptionalExtension};
+use serde::{de::DeserializeOwned, Serialize};
+
+use crate::{config::CacheConfig, error::BadgifyError};
+
+/// A successful result and the unix time it was read at.
+pub struct StaleResult<T> {
+    pub result: Arc<T>,
+    pub fetched_at: u64,
+}
+
+impl<T> Clone for StaleResult<T> {
+    fn clone(&self) -> Self {
+        Self {
+            result: self.result.clone(),
+            fetched_at: self.fetched_at,
+        }
+    }
+}
+
+/// A serialized result waiting to be persisted: the query, the time it was
+/// read at and the result as JSON.
+type PendingWrite = (String, u64, String);
+
+/// Keeps the last successful result of every query, so that it can be served
+/// while upstreams fail. Results are optionally written through to SQLite to
+/// survive restarts, by a background thread so that requests do not wait
+/// for the disk.
+pub struct StaleStore<T> {
+    max_age_secs: u64,
+    entries: Mutex<LruCache<String, StaleResult<T>>>,
+    store: Option<Arc<Mutex<Connection>>>,
+    writes: Option<mpsc::Sender<PendingWrite>>,
+    writer: Option<JoinHandle<()>>,
+}
+
+impl<T: Serialize + DeserializeOwned + Send + Sync + 'static> StaleStore<T> {
+    pub fn open(config: &CacheConfig) -> Result<Self, BadgifyError> {
+        let store = match &config.stale_database_path {
+            Some(path) if config.stale_max_age_secs > 0 => {
+                Some(Arc::new(Mutex::new(open_store(path)?)))
+            }
+            _ => None,
+        };
+
+        let (writes, writer) = match &store {
+            Some(store) => {
+                let (writes, pending) = mpsc::channel();
+                let store = store.clone();
+                let writer = std::thread::Builder::new()
+                    .name("stale-store-writer".to_string())
+                    .spawn(move || write_results(&store, &pending))
+                    .map_err(|err| {
+                        BadgifyError::Internal(format!("Cannot start stale store writer: {err}"))
+                    })?;
+                (Some(writes), Some(writer))
+            }
+            None => (None, None),
+        };
+
+        Ok(Self {
+            max_age_secs: config.stale_max_age_secs,
+            entries: Mutex::new(LruCache::new(
+                NonZeroUsize::new(config.max_entries).unwrap_or(NonZeroUsize::MIN),
+            )),
+            store,
+            writes,
+            writer,
+        })
+    }
+
+    pub fn record(&self, key: &str, result: Arc<T>, fetched_at: u64) {
+        if self.max_age_secs == 0 {
+            return;
+        }
+
+        if let Some(writes) = &self.writes {
+            match serde_json::to_string(&*result) {
+                Ok(json) => {
+                    // Only fails once the writer has died, which it logged.
+                    let _ = writes.send((key.to_string(), fetched_at, json));
+                }
+                Err(err) => println!("Failed to serialize result of {key}: {err}"),
+            }
+        }
+
+        self.entries
+            .lock()
+            .unwrap()
+            .put(key.to_string(), StaleResult { result, fetched_at });
+    }
+
+    /// Returns the last result of `key` unless it is older than the maximum age.
+    pub async fn get(&self, key: &str, now: u64) -> Option<StaleResult<T>> {
+        if self.max_age_secs == 0 {
+            return None;
+        }
+
+        let cached = self.entries.lock().unwrap().get(key).cloned();
+        let stale = match cached {
+            Some(stale) => stale,
+            None => {
+                let stale = self.read_persisted(key).await?;
+                self.entries
+                    .lock()
+                    .unwrap()
+                    .put(key.to_string(), stale.clone());
+                stale
+            }
+        };
+
+        (now.saturating_sub(stale.fetched_at) <= self.max_age_secs).then_some(stale)
+    }
+
+    /// Reads `key` on the blocking thread pool, so that SQLite does not stall
+    /// the async workers serving requests.
+    async fn read_persisted(&self, key: &str) -> Option<StaleResult<T>> {
+        let store = self.store.clone()?;
+        let query = key.to_string();
+
+        let read = tokio::task::spawn_blocking(move || read_result(&store.lock().unwrap(), &query))
+            .await
+            .map_err(|err| BadgifyError::Internal(format!("Stale store task failed: {err}")));
+
+        match read.and_then(|read| read) {
+            Ok(stale) => stale,
+            Err(err) => {
+                println!("Failed to read persisted result of {key}: {err}");
+                None
+            }
+        }
+    }
+}
+
+impl<T> Drop for StaleStore<T> {
+    /// Waits for pending writes, so that they are not lost on shutdown.
+    fn drop(&mut self) {
+        self.writes.take();
+
+        if let Some(writer) = self.writer.take() {
+            let _ = writer.join();
+        }
+    }
+}
+
+fn open_store(path: &Path) -> Result<Connection, BadgifyError> {
+    let connection = Connection::open(path)?;
+
+    connection.execute_batch(
+        "CREATE TABLE IF NOT EXISTS last_results (
+            query TEXT PRIMARY KEY,
+            fetched_at INTEGER NOT NULL,
+            result TEXT NOT NULL
+        );",
+    )?;
+
+    Ok(connection)
+}
+
+/// Persists results until the store is dropped. Results that queue up while
+/// a batch is written are committed together in the next one.
+fn write_results(store: &Mutex<Connection>, pending: &mpsc::Receiver<PendingWrite>) {
+    while let Ok(first) = pending.recv() {
+        let batch: Vec<PendingWrite> = std::iter::once(first).chain(pending.try_iter()).collect();
+
+        if let Err(err) = write_batch(&mut store.lock().unwrap(), &batch) {
+            println!("Failed to persist {} results: {err}", batch.len());
+        }
+    }
+}
+
+fn write_batch(store: &mut Connection, batch: &[PendingWrite]) -> Result<(), BadgifyError> {
+    let tx = store.transaction()?;
+
+    for (key, fetched_at, result) in batch {
+        tx.execute(
+            "INSERT INTO last_results (query, fetched_at, result) VALUES (?1, ?2, ?3)
+            ON CONFLICT (query) DO UPDATE SET
+                fetched_at = excluded.fetched_at, result = excluded.result",
+            params![key, fetched_at, result],
+        )?;
+    }
+
+    tx.commit()?;
+
+    Ok(())
+}
+
+fn read_result<T: DeserializeOwned>(
+    store: &Connection,
+    key: &str,
+) -> Result<Option<StaleResult<T>>, BadgifyError> {
+    let row: Option<(u64, String)> = store
+        .query_row(
+            "SELECT fetched_at, result FROM last_results WHERE query = ?1",
+            params![key],
+            |row| Ok((row.get(0)?, row.get(1)?)),
+        )
+        .optional()?;
+
+    row.map(|(fetched_at, result)| {
+        Ok(StaleResult {
+            result: Arc::new(serde_json::from_str(&result)?),
+            fetched_at,
+        })
+    })
+    .transpose()
+}
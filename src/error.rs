@@ -0,0 +1,254 @@
+use std::fmt::Display;
+
+use actix_web::http::StatusCode;
+use alloy::transports::{RpcError, TransportError, TransportErrorKind};
+
+use crate::{
+    data_source::evm_quorum::QuorumError, query::QueryParseError, types::ChainID, utils::strip_urls,
+};
+
+/// Everything that can go wrong while answering a query. Messages never hold
+/// URLs, which may contain API keys.
+#[derive(Debug, Clone)]
+pub enum BadgifyError {
+    /// The query path is malformed.
+    InvalidQuery(QueryParseError),
+    /// The chain is not in the chain list.
+    UnknownChain(ChainID),
+    /// What the query asks for does not exist, e.g. an unregistered ENS name.
+    NotFound(String),
+    /// The query cannot be answered here, e.g. a chain without ENS or a token
+    /// that is not indexed.
+    Unavailable(String),
+    /// No RPC endpoint is known or usable for the chain.
+    NoRpc(String),
+    /// An RPC endpoint or API failed or answered with an error.
+    Upstream(String),
+    UpstreamTimeout,
+    RateLimited,
+    /// An answer could not be decoded.
+    Decoding(String),
+    /// A contract call reverted or returned data that does not match its ABI,
+    /// e.g. an ERC20 call to an account without code. Every endpoint would
+    /// answer the same.
+    CallFailed(String),
+    /// RPC endpoints disagreed on the answer.
+    Quorum(QuorumError),
+    Internal(String),
+}
+
+impl BadgifyError {
+    /// Stable identifier of the error for API clients.
+    pub fn code(&self) -> &'static str {
+        match self {
+            Self::InvalidQuery(_) => "invalid_query",
+            Self::UnknownChain(_) => "unknown_chain",
+            Self::NotFound(_) => "not_found",
+            Self::Unavailable(_) => "unavailable",
+            Self::NoRpc(_) => "no_rpc",
+            Self::Upstream(_) => "upstream_error",
+            Self::UpstreamTimeout => "upstream_timeout",
+            Self::RateLimited => "rate_limited",
+            Self::Decoding(_) => "decoding_error",
+            Self::CallFailed(_) => "call_failed",
+            Self::Quorum(_) => "quorum_failed",
+            Self::Internal(_) => "internal_error",
+        }
+    }
+
+    pub fn status_code(&self) -> StatusCode {
+        match self {
+            Self::InvalidQuery(_) => StatusCode::BAD_REQUEST,
+            Self::UnknownChain(_) | Self::NotFound(_) | Self::Unavailable(_) => {
+                StatusCode::NOT_FOUND
+            }
+            Self::NoRpc(_) | Self::Upstream(_) | Self::Decoding(_) | Self::Quorum(_) => {
+                StatusCode::BAD_GATEWAY
+            }
+            Self::CallFailed(_) => StatusCode::UNPROCESSABLE_ENTITY,
+            Self::UpstreamTimeout => StatusCode::GATEWAY_TIMEOUT,
+            Self::RateLimited => StatusCode::TOO_MANY_REQUESTS,
+            Self::Internal(_) => StatusCode::INTERNAL_SERVER_ERROR,
+        }
+    }
+
+    /// Short message shown on error badges.
+    pub fn badge_message(&self) -> String {
+        match self {
+            Self::InvalidQuery(err) => format!("bad {}", err.kind.subject()),
+            Self::UnknownChain(_) => "chain unknown".to_string(),
+            Self::NotFound(_) => "not found".to_string(),
+            Self::Unavailable(_) => "unavailable".to_string(),
+            Self::NoRpc(_) | Self::Upstream(_) => "RPC down".to_string(),
+            Self::UpstreamTimeout => "timeout".to_string(),
+            Self::RateLimited => "rate limited".to_string(),
+            Self::Decoding(_) => "bad response".to_string(),
+            Self::CallFailed(_) => "call failed".to_string(),
+            Self::Quorum(_) => "RPC mismatch".to_string(),
+            Self::Internal(_) => "failed".to_string(),
+        }
+    }
+
+    /// Message for API clients. Details of upstream failures are only logged.
+    pub fn public_message(&self) -> String {
+        match self {
+            Self::InvalidQuery(_)
+            | Self::UnknownChain(_)
+            | Self::NotFound(_)
+            | Self::Unavailable(_)
+            | Self::CallFailed(_)
+            | Self::UpstreamTimeout
+            | Self::RateLimited => self.to_string(),
+            Self::NoRpc(_) => "No RPC endpoint is available".to_string(),
+            Self::Upstream(_) => "Upstream request failed".to_string(),
+            Self::Decoding(_) => "Cannot decode upstream response".to_string(),
+            Self::Quorum(_) => "RPC endpoints disagree".to_string(),
+            Self::Internal(_) => "Internal error".to_string(),
+        }
+    }
+
+    /// Whether another RPC endpoint may succeed where this error occurred.
+    /// Other errors are answers that every endpoint would give.
+    pub fn is_endpoint_failure(&self) -> bool {
+        matches!(
+            self,
+            Self::NoRpc(_)
+                | Self::Upstream(_)
+                | Self::UpstreamTimeout
+                | Self::RateLimited
+                | Self::Decoding(_)
+        )
+    }
+}
+
+impl Display for BadgifyError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::InvalidQuery(err) => write!(f, "{err}"),
+            Self::UnknownChain(chain_id) => write!(f, "Chain {chain_id} not found"),
+            Self::NotFound(message)
+            | Self::Unavailable(message)
+            | Self::NoRpc(message)
+            | Self::Upstream(message)
+            | Self::Internal(message) => write!(f, "{message}"),
+            Self::UpstreamTimeout => write!(f, "Upstream request timed out"),
+            Self::RateLimited => write!(f, "Rate limited by upstream"),
+            Self::Decoding(message) => write!(f, "Cannot decode response: {message}"),
+            Self::CallFailed(message) => write!(f, "Contract call failed: {message}"),
+            Self::Quorum(err) => write!(f, "{err}"),
+        }
+    }
+}
+
+impl std::error::Error for BadgifyError {}
+
+impl From<QueryParseError> for BadgifyError {
+    fn from(err: QueryParseError) -> Self {
+        Self::InvalidQuery(err)
+    }
+}
+
+impl From<QuorumError> for BadgifyError {
+    fn from(err: QuorumError) -> Self {
+        Self::Quorum(err)
+    }
+}
+
+impl From<TransportError> for BadgifyError {
+    fn from(err: TransportError) -> Self {
+        match &err {
+            RpcError::Transport(TransportErrorKind::HttpError(http_err))
+                if http_err.is_rate_limit_err() =>
+            {
+                Self::RateLimited
+            }
+            RpcError::Transport(TransportErrorKind::Custom(custom)) => {
+                match custom.downcast_ref::<reqwest::Error>() {
+                    Some(reqwest_err) => reqwest_err_kind(reqwest_err),
+                    None => Self::Upstream(strip_urls(&err.to_string())),
+                }
+            }
+            RpcError::ErrorResp(payload)
+                if payload.code == 429 || payload.message.to_lowercase().contains("rate limit") =>
+            {
+                Self::RateLimited
+            }
+            RpcError::ErrorResp(payload) if is_execution_error(payload.code, &payload.message) => {
+                Self::CallFailed(payload.message.to_string())
+            }
+            RpcError::DeserError { .. } | RpcError::NullResp => {
+                Self::Decoding(strip_urls(&err.to_string()))
+            }
+            _ => Self::Upstream(strip_urls(&err.to_string())),
+        }
+    }
+}
+
+impl From<reqwest::Error> for BadgifyError {
+    fn from(err: reqwest::Error) -> Self {
+        reqwest_err_kind(&err.without_url())
+    }
+}
+
+impl From<alloy::sol_types::Error> for BadgifyError {
+    fn from(err: alloy::sol_types::Error) -> Self {
+        Self::CallFailed(err.to_string())
+    }
+}
+
+impl<T> From<alloy::primitives::ruint::FromUintError<T>> for BadgifyError {
+    fn from(err: alloy::primitives::ruint::FromUintError<T>) -> Self {
+        Self::Decoding(err.to_string())
+    }
+}
+
+impl From<alloy::primitives::BigIntConversionError> for BadgifyError {
+    fn from(err: alloy::primitives::BigIntConversionError) -> Self {
+        Self::Decoding(err.to_string())
+    }
+}
+
+impl From<alloy::primitives::ruint::ParseError> for BadgifyError {
+    fn from(err: alloy::primitives::ruint::ParseError) -> Self {
+        Self::Decoding(err.to_string())
+    }
+}
+
+impl From<alloy::hex::FromHexError> for BadgifyError {
+    fn from(err: alloy::hex::FromHexError) -> Self {
+        Self::Decoding(err.to_string())
+    }
+}
+
+impl From<serde_json::Error> for BadgifyError {
+    fn from(err: serde_json::Error) -> Self {
+        Self::Decoding(err.to_string())
+    }
+}
+
+impl From<rusqlite::Error> for BadgifyError {
+    fn from(err: rusqlite::Error) -> Self {
+        Self::Internal(format!("Database error: {err}"))
+    }
+}
+
+/// Whether a JSON-RPC error reports the EVM failing to execute a call, as
+/// opposed to the node failing to serve it.
+fn is_execution_error(code: i64, message: &str) -> bool {
+    const EXECUTION_ERRORS: [&str; 4] = ["revert", "invalid opcode", "out of gas", "execution"];
+
+    let message = message.to_lowercase();
+    code == 3 || EXECUTION_ERRORS.iter().any(|error| message.contains(error))
+}
+
+fn reqwest_err_kind(err: &reqwest::Error) -> BadgifyError {
+    if err.is_timeout() {
+        BadgifyError::UpstreamTimeout
+    } else if err.status() == Some(reqwest::StatusCode::TOO_MANY_REQUESTS) {
+        BadgifyError::RateLimited
+    } else if err.is_decode() {
+        BadgifyError::Decoding(strip_urls(&err.to_string()))
+    } else {
+        BadgifyError::Upstream(strip_urls(&err.to_string()))
+    }
+}
@@ -0,0 +1,313 @@
+use std::{
+    collections::HashMap,
+    error::Error,
+    path::PathBuf,
+    sync::{Arc, RwLock},
+    time::{Duration, Instant},
+};
+
+use base64::{prelude::BASE64_STANDARD, Engine};
+use serde::Deserialize;
+use tokio::sync::watch;
+
+use crate::config::ChainListConfig;
+
+const ICONS_URL: &str =
+    "https://raw.githubusercontent.com/ethereum-lists/chains/master/_data/icons";
+// How soon an icon that could not be loaded is tried again.
+const RETRY_INTERVAL: Duration = Duration::from_secs(5 * 60);
+const FETCH_TIMEOUT: Duration = Duration::from_secs(5);
+// How long a badge waits for its icon before being served without it.
+const ICON_WAIT: Duration = Duration::from_millis(300);
+// Shields rejects badges with very large logos, so bigger images are skipped.
+const MAX_IMAGE_SIZE: usize = 64 * 1024;
+const DEFAULT_ICON_SIZE: u32 = 32;
+
+/// One image of a chainlist icon definition (`_data/icons/<key>.json`).
+#[derive(Debug, Deserialize, Clone)]
+pub struct IconImage {
+    pub url: String,
+    pub width: Option<u32>,
+    pub height: Option<u32>,
+    pub format: String,
+}
+
+/// Resolves chainlist icon keys (`EvmChain::icon`) to SVGs that badges can
+/// embed, caching every icon once loaded.
+pub struct ChainIcons {
+    loader: Arc<IconLoader>,
+    cache: Arc<RwLock<HashMap<String, CachedIcon>>>,
+}
+
+enum CachedIcon {
+    Loaded(Arc<String>),
+    /// Changes once the load in progress finishes.
+    Loading(watch::Receiver<()>),
+    Failed(Instant),
+}
+
+/// Where icon definitions are read from.
+enum IconSource {
+    Url(String),
+    Directory(PathBuf),
+}
+
+struct IconLoader {
+    source: IconSource,
+    ipfs_gateway: String,
+    client: reqwest::Client,
+}
+
+impl ChainIcons {
+    pub fn new(config: &ChainListConfig) -> Self {
+        let source = match &config.icons_source {
+            Some(source) if source.starts_with("http") => {
+                IconSource::Url(source.trim_end_matches('/').to_string())
+            }
+            Some(path) => IconSource::Directory(PathBuf::from(path)),
+            None => IconSource::Url(ICONS_URL.to_string()),
+        };
+
+        Self {
+            loader: Arc::new(IconLoader {
+                source,
+                ipfs_gateway: config.ipfs_gateway.trim_end_matches('/').to_string(),
+                client: reqwest::Client::new(),
+            }),
+            cache: Arc::new(RwLock::new(HashMap::new())),
+        }
+    }
+
+    /// Returns the icon named `key` as an SVG document. Icons are loaded in
+    /// the background on first use, once however many badges ask for them,
+    /// and only waited for briefly so that a slow icon host does not hold up
+    /// badges, which go without the icon meanwhile. Icons that cannot be
+    /// loaded are not retried for a while.
+    pub async fn svg(&self, key: &str) -> Option<Arc<String>> {
+        let loading = match self.cache.read().unwrap().get(key) {
+            Some(CachedIcon::Loaded(svg)) => return Some(svg.clone()),
+            Some(CachedIcon::Failed(at)) if at.elapsed() < RETRY_INTERVAL => return None,
+            Some(CachedIcon::Loading(loaded)) if is_pending(loaded) => Some(loaded.clone()),
+            _ => None,
+        };
+
+        let mut loaded = match loading {
+            Some(loaded) => loaded,
+            None => self.start_loading(key)?,
+        };
+
+        // Finishing drops the sender, so this wakes up either way.
+        let _ = tokio::time::timeout(ICON_WAIT, loaded.changed()).await;
+
+        match self.cache.read().unwrap().get(key) {
+            Some(CachedIcon::Loaded(svg)) => Some(svg.clone()),
+            _ => None,
+        }
+    }
+
+    /// Starts loading `key` unless that already happened meanwhile, and
+    /// returns a receiver that changes once it is done.
+    fn start_loading(&self, key: &str) -> Option<watch::Receiver<()>> {
+        let mut cache = self.cache.write().unwrap();
+
+        match cache.get(key) {
+            Some(CachedIcon::Loaded(_)) => return None,
+            Some(CachedIcon::Loading(loaded)) if is_pending(loaded) => return Some(loaded.clone()),
+            Some(CachedIcon::Failed(at)) if at.elapsed() < RETRY_INTERVAL => return None,
+            _ => {}
+        }
+
+        let (done, loaded) = watch::channel(());
+        cache.insert(key.to_string(), CachedIcon::Loading(loaded.clone()));
+
+        let loader = self.loader.clone();
+        let cache = self.cache.clone();
+        let key = key.to_string();
+
+        tokio::spawn(async move {
+            let icon = match loader.load(&key).await {
+                Ok(svg) => CachedIcon::Loaded(Arc::new(svg)),
+                Err(err) => {
+                    println!("Cannot load chain icon {key}: {err}");
+                    CachedIcon::Failed(Instant::now())
+                }
+            };
+
+            cache.write().unwrap().insert(key, icon);
+            drop(done);
+        });
+
+        Some(loaded)
+    }
+}
+
+/// Whether a load is still running; a load that panicked drops its sender
+/// without updating the cache, and is started again.
+fn is_pending(loaded: &watch::Receiver<()>) -> bool {
+    loaded.has_changed().is_ok()
+}
+
+impl IconLoader {
+    async fn load(&self, key: &str) -> Result<String, Box<dyn Error + Send + Sync>> {
+        if !key
+            .chars()
+            .all(|c| c.is_ascii_alphanumeric() || c == '-' || c == '_')
+        {
+            return Err("Invalid icon key".into());
+        }
+
+        let images: Vec<IconImage> = match &self.source {
+            IconSource::Url(url) => {
+                serde_json::from_slice(&self.fetch(&format!("{url}/{key}.json")).await?)?
+            }
+            IconSource::Directory(path) => serde_json::from_str(
+                &tokio::fs::read_to_string(path.join(format!("{key}.json"))).await?,
+            )?,
+        };
+
+        // Prefer vector images, they stay sharp at any badge size.
+        let image = images
+            .iter()
+            .find(|image| image.format == "svg")
+            .or_else(|| images.first())
+            .ok_or("Icon has no images")?;
+
+        let bytes = self.fetch(&self.resolve_url(&image.url)).await?;
+
+        Ok(to_svg(image, &bytes).map_err(|err| err.to_string())?)
+    }
+
+    /// Downloads `url`, giving up as soon as the body exceeds
+    /// [`MAX_IMAGE_SIZE`] rather than reading it all first.
+    async fn fetch(&self, url: &str) -> Result<Vec<u8>, Box<dyn Error + Send + Sync>> {
+        let too_large = || format!("Icon is larger than {MAX_IMAGE_SIZE} bytes").into();
+
+        let mut response = self
+            .client
+            .get(url)
+            .timeout(FETCH_TIMEOUT)
+            .send()
+            .await?
+            .error_for_status()?;
+
+        if response
+            .content_length()
+            .is_some_and(|length| length > MAX_IMAGE_SIZE as u64)
+        {
+            return Err(too_large());
+        }
+
+        let mut body = Vec::new();
+        while let Some(chunk) = response.chunk().await? {
+            if body.len() + chunk.len() > MAX_IMAGE_SIZE {
+                return Err(too_large());
+            }
+            body.extend_from_slice(&chunk);
+        }
+
+        Ok(body)
+    }
+
+    fn resolve_url(&self, url: &str) -> String {
+        match url.strip_prefix("ipfs://") {
+            Some(cid) => format!("{}/{cid}", self.ipfs_gateway),
+            None => url.to_string(),
+        }
+    }
+}
+
+/// Converts the downloaded `bytes` of `image` to an SVG document. Raster
+/// images are embedded in an SVG as a data URI.
+pub fn to_svg(image: &IconImage, bytes: &[u8]) -> Result<String, Box<dyn Error>> {
+    let mime_type = match image.format.as_str() {
+        "svg" => {
+            let svg = std::str::from_utf8(bytes)?;
+            if !svg.contains("<svg") {
+                return Err("Icon is not an SVG document".into());
+            }
+            if !is_safe_svg(svg) {
+                return Err("Icon has scripts or external references".into());
+            }
+
+            return Ok(svg.to_string());
+        }
+        "png" => "image/png",
+        "jpg" | "jpeg" => "image/jpeg",
+        "webp" => "image/webp",
+        format => return Err(format!("Unsupported icon format {format}").into()),
+    };
+
+    let width = image.width.unwrap_or(DEFAULT_ICON_SIZE);
+    let height = image.height.unwrap_or(DEFAULT_ICON_SIZE);
+
+    Ok(format!(
+        r#"<svg xmlns="http://www.w3.org/2000/svg" width="{width}" height="{height}" viewBox="0 0 {width} {height}"><image width="{width}" height="{height}" href="data:{mime_type};base64,{}"/></svg>"#,
+        BASE64_STANDARD.encode(bytes)
+    ))
+}
+
+/// Whether `svg` is free of anything that could run or load content when
+/// the icon is embedded: scripts, event handlers, embedded documents,
+/// entity declarations and references to anything but its own elements or
+/// inline images. Third-party icons failing this are not used at all
+/// rather than cleaned up.
+pub fn is_safe_svg(svg: &str) -> bool {
+    // Embedded SVGs could nest anything, so only raster images are allowed.
+    const RASTER_DATA_URIS: [&str; 4] = [
+        "data:image/png",
+        "data:image/jpeg",
+        "data:image/webp",
+        "data:image/gif",
+    ];
+    const FORBIDDEN: [&str; 8] = [
+        "<script",
+        "<foreignobject",
+        "<iframe",
+        "<embed",
+        "<object",
+        "<!entity",
+        "<!doctype",
+        "javascript:",
+    ];
+
+    let svg = svg.to_ascii_lowercase();
+
+    if FORBIDDEN.iter().any(|forbidden| svg.contains(forbidden)) {
+        return false;
+    }
+
+    let references_are_local = attribute_values(&svg, "href")
+        .chain(svg.split("url(").skip(1))
+        .all(|value| {
+            let value = value.trim_start_matches(['"', '\'', ' ']);
+            value.starts_with('#')
+                || RASTER_DATA_URIS
+                    .iter()
+                    .any(|prefix| value.starts_with(prefix))
+        });
+
+    references_are_local && !has_event_handler(&svg)
+}
+
+/// Returns what follows `="` or `='` after every `name` attribute in `svg`.
+fn attribute_values<'a>(svg: &'a str, name: &'a str) -> impl Iterator<Item = &'a str> {
+    svg.match_indices(name).filter_map(move |(index, _)| {
+        let value = svg[index + name.len()..].trim_start().strip_prefix('=')?;
+        Some(value.trim_start())
+    })
+}
+
+/// Whether `svg` has an `on...=` attribute, such as `onload`.
+fn has_event_handler(svg: &str) -> bool {
+    svg.match_indices("on").any(|(index, _)| {
+        let preceded_by_space = svg[..index]
+            .chars()
+            .next_back()
+            .is_some_and(|c| c.is_ascii_whitespace() || c == '/');
+        let rest = svg[index + 2..].trim_start_matches(|c: char| c.is_ascii_alphabetic());
+
+        preceded_by_space
+            && rest.len() < svg.len() - index - 2
+            && rest.trim_start().starts_with('=')
+    })
+}
@@ -1,16 +1,25 @@
 use std::{
-    sync::Arc,
-    time::{SystemTime, SystemTimeError},
+    collections::HashMap,
+    error::Error,
+    path::PathBuf,
+    sync::{Arc, RwLock},
+    time::Duration,
 };
-use tokio::sync::{RwLock, RwLockReadGuard};
+use tokio::sync::Mutex;
 
 use alloy::primitives::U256;
 use serde::{Deserialize, Serialize};
 
-use crate::types::ChainID;
+use crate::{
+    config::ChainListConfig,
+    types::{ChainID, EvmAddress},
+};
 
 const CHAIN_LIST_URL: &str = "https://chainid.network/chains.json";
-const REFRESH_INTERVAL: u64 = 24 * 60 * 60;
+const BUNDLED_CHAIN_LIST: &str = include_str!("../data/chains.json");
+// How soon a failed refresh is retried; the previous list is served meanwhile.
+const RETRY_INTERVAL: Duration = Duration::from_secs(5 * 60);
+const FETCH_TIMEOUT: Duration = Duration::from_secs(10);
 
 #[derive(Debug, Serialize, Deserialize, Clone)]
 #[serde(rename_all = "camelCase")]
@@ -18,26 +27,30 @@ pub struct EvmChain {
     pub name: String,
     pub chain: String,
     pub icon: Option<String>,
-    #[serde(skip_serializing)]
+    pub title: Option<String>,
+    #[serde(default, skip_serializing)]
     pub rpc: Vec<String>,
-    // #[serde(skip_serializing)]
-    // pub features: Option<Vec<Feature>>,
-    // #[serde(skip_serializing)]
-    // pub faucets: Vec<String>,
+    #[serde(default)]
+    pub features: Vec<Feature>,
+    #[serde(default, skip_serializing)]
+    pub faucets: Vec<String>,
     pub native_currency: NativeCurrency,
-    // pub info_url: Option<String>,
+    #[serde(rename = "infoURL")]
+    pub info_url: Option<String>,
     pub short_name: String,
     pub chain_id: U256,
     pub network_id: U256,
     pub slip44: Option<u64>,
-    // pub ens: Option<Ens>,
+    pub ens: Option<Ens>,
+    pub parent: Option<ParentChain>,
+    pub status: Option<ChainStatus>,
     #[serde(skip_serializing)]
     pub explorers: Option<Vec<Explorer>>,
 }
 
 #[derive(Debug, Serialize, Deserialize, Clone)]
 pub struct Feature {
-    name: String,
+    pub name: String,
 }
 
 #[derive(Debug, Serialize, Deserialize, Clone)]
@@ -49,7 +62,32 @@ pub struct NativeCurrency {
 
 #[derive(Debug, Serialize, Deserialize, Clone)]
 pub struct Ens {
-    registry: String,
+    pub registry: EvmAddress,
+}
+
+/// The chain an L2 settles on, e.g. `eip155-1` for Ethereum Mainnet.
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct ParentChain {
+    #[serde(rename = "type")]
+    pub kind: String,
+    pub chain: String,
+    #[serde(default)]
+    pub bridges: Vec<Bridge>,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct Bridge {
+    pub url: String,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, PartialEq)]
+#[serde(rename_all = "lowercase")]
+pub enum ChainStatus {
+    Active,
+    Incubating,
+    Deprecated,
+    #[serde(other)]
+    Unknown,
 }
 
 #[derive(Debug, Serialize, Deserialize, Clone)]
@@ -60,82 +98,172 @@ pub struct Explorer {
     pub standard: String,
 }
 
+impl EvmChain {
+    /// Chainlist has no testnet flag, so testnets are recognised by the
+    /// testnet coin type of SLIP-44 or by their name.
+    pub fn is_testnet(&self) -> bool {
+        self.slip44 == Some(1)
+            || [Some(&self.name), self.title.as_ref()]
+                .into_iter()
+                .flatten()
+                .any(|name| name.to_lowercase().contains("testnet"))
+    }
+
+    pub fn is_deprecated(&self) -> bool {
+        self.status == Some(ChainStatus::Deprecated)
+    }
+
+    pub fn supports_eip1559(&self) -> bool {
+        self.features
+            .iter()
+            .any(|feature| feature.name == "EIP1559")
+    }
+
+    /// Returns the chain ID of the chain an L2 settles on.
+    pub fn parent_chain_id(&self) -> Option<ChainID> {
+        self.parent
+            .as_ref()?
+            .chain
+            .strip_prefix("eip155-")?
+            .parse()
+            .ok()
+    }
+}
+
 pub struct EvmChainList {
-    data: Arc<RwLock<EvmChainListData>>,
+    index: RwLock<Arc<ChainIndex>>,
+    refresh_lock: Mutex<()>,
+    source: ChainListSource,
+    refresh_interval: Duration,
 }
 
-#[derive(Default, Clone)]
-pub struct EvmChainListData {
-    list: Option<Vec<EvmChain>>,
-    last_fetch_at: Option<SystemTime>,
+/// The chain list indexed by chain ID and by lowercase short name.
+#[derive(Default)]
+struct ChainIndex {
+    by_id: HashMap<ChainID, Arc<EvmChain>>,
+    by_short_name: HashMap<String, ChainID>,
 }
 
-impl Default for EvmChainList {
-    fn default() -> Self {
-        Self {
-            data: Arc::new(RwLock::new(EvmChainListData::default())),
-        }
-    }
+/// Where the chain list is refreshed from.
+enum ChainListSource {
+    Url(String),
+    File(PathBuf),
 }
 
-impl EvmChainListData {
-    pub fn get_chain(&self, chain_id: ChainID) -> Option<EvmChain> {
-        if let Some(chain_list) = &self.list {
-            chain_list.iter().find(|c| c.chain_id == chain_id).cloned()
-        } else {
-            None
+impl ChainIndex {
+    fn new(list: Vec<EvmChain>) -> Self {
+        let mut index = Self::default();
+
+        for chain in list {
+            index
+                .by_short_name
+                .insert(chain.short_name.to_lowercase(), chain.chain_id);
+            index.by_id.insert(chain.chain_id, Arc::new(chain));
         }
+
+        index
     }
 }
 
 impl EvmChainList {
-    pub async fn fetch_evm_chain(
-        &self,
-        chain_id: ChainID,
-    ) -> Result<Option<EvmChain>, Box<dyn std::error::Error>> {
-        let data_read = self.fetch_chain_list().await?;
-        Ok(data_read.get_chain(chain_id))
+    /// Starts out with the bundled snapshot, so that chains can be served
+    /// before the first refresh from `config.source` succeeds.
+    pub fn new(config: &ChainListConfig) -> Result<Self, Box<dyn Error>> {
+        let source = match &config.source {
+            Some(source) if source.starts_with("http") => ChainListSource::Url(source.clone()),
+            Some(path) => ChainListSource::File(PathBuf::from(path)),
+            None => ChainListSource::Url(CHAIN_LIST_URL.to_string()),
+        };
+
+        Ok(Self {
+            index: RwLock::new(Arc::new(ChainIndex::new(bundled_chain_list()?))),
+            refresh_lock: Mutex::new(()),
+            source,
+            refresh_interval: Duration::from_secs(config.refresh_interval_secs),
+        })
     }
 
-    async fn fetch_chain_list(
-        &self,
-    ) -> Result<RwLockReadGuard<'_, EvmChainListData>, SystemTimeError> {
-        let chain_list_data = {
-            let chainlist_data = self.data.read().await;
+    pub fn get_chain(&self, chain_id: ChainID) -> Option<Arc<EvmChain>> {
+        self.index.read().unwrap().by_id.get(&chain_id).cloned()
+    }
+
+    pub fn get_chain_by_short_name(&self, short_name: &str) -> Option<Arc<EvmChain>> {
+        let index = self.index.read().unwrap();
+
+        index
+            .by_short_name
+            .get(&short_name.to_lowercase())
+            .and_then(|chain_id| index.by_id.get(chain_id))
+            .cloned()
+    }
+
+    /// Returns every known chain, ordered by chain ID.
+    pub fn chains(&self) -> Vec<Arc<EvmChain>> {
+        let mut chains: Vec<_> = self.index.read().unwrap().by_id.values().cloned().collect();
+        chains.sort_by_key(|chain| chain.chain_id);
+        chains
+    }
 
-            let refresh_required = match chainlist_data.last_fetch_at {
-                Some(last_fetched_at) => {
-                    SystemTime::now().duration_since(last_fetched_at)?.as_secs() > REFRESH_INTERVAL
+    /// Refreshes the chain list forever. After a failed refresh the previous
+    /// list is kept and the refresh is retried sooner.
+    pub async fn run(self: Arc<Self>) {
+        loop {
+            let delay = match self.refresh().await {
+                Ok(()) => self.refresh_interval,
+                Err(err) => {
+                    println!("Cannot refresh EVM chain list, serving the previous one: {err}");
+                    RETRY_INTERVAL
                 }
-                None => true,
             };
 
-            if refresh_required {
-                None
-            } else {
-                Some(chainlist_data)
-            }
+            tokio::time::sleep(delay).await;
+        }
+    }
+
+    /// Reloads the chain list from its source. Callers that arrive while a
+    /// refresh is in flight wait for it instead of starting another one.
+    pub async fn refresh(&self) -> Result<(), Box<dyn Error>> {
+        let Ok(_guard) = self.refresh_lock.try_lock() else {
+            let _ = self.refresh_lock.lock().await;
+            return Ok(());
         };
 
-        match chain_list_data {
-            Some(chain_list_data) => Ok(chain_list_data),
-            None => Ok(self.update_chain_list().await),
-        }
+        let index = ChainIndex::new(self.source.load().await?);
+        *self.index.write().unwrap() = Arc::new(index);
+
+        Ok(())
     }
+}
 
-    async fn update_chain_list(&self) -> RwLockReadGuard<'_, EvmChainListData> {
-        let new_chain_list_data = fetch_evm_chainlist().await.unwrap();
+impl ChainListSource {
+    async fn load(&self) -> Result<Vec<EvmChain>, Box<dyn Error>> {
+        let chain_list: Vec<EvmChain> = match self {
+            Self::Url(url) => {
+                println!("Fetching EVM Chainlist from {url}");
+                reqwest::Client::new()
+                    .get(url)
+                    .timeout(FETCH_TIMEOUT)
+                    .send()
+                    .await?
+                    .error_for_status()?
+                    .json()
+                    .await?
+            }
+            Self::File(path) => {
+                println!("Loading EVM Chainlist from {}", path.display());
+                serde_json::from_str(&tokio::fs::read_to_string(path).await?)?
+            }
+        };
 
-        let mut chain_list_data = self.data.write().await;
-        chain_list_data.list = Some(new_chain_list_data);
-        chain_list_data.last_fetch_at = Some(SystemTime::now());
+        if chain_list.is_empty() {
+            return Err("Chain list is empty".into());
+        }
 
-        // Downgrade the write lock to a read lock and return it
-        chain_list_data.downgrade()
+        Ok(chain_list)
     }
 }
 
-async fn fetch_evm_chainlist() -> Result<Vec<EvmChain>, Box<dyn std::error::Error>> {
-    println!("Fetching EVM Chainlist");
-    Ok(reqwest::get(CHAIN_LIST_URL).await?.json().await?)
+/// Parses the snapshot of chainid.network embedded in the binary.
+pub fn bundled_chain_list() -> Result<Vec<EvmChain>, Box<dyn Error>> {
+    Ok(serde_json::from_str(BUNDLED_CHAIN_LIST)?)
 }
@@ -0,0 +1,164 @@
+use std::{
+    sync::{Arc, Mutex},
+    time::Duration,
+};
+
+use rusqlite::{params, Connection};
+use serde::Serialize;
+
+use crate::{
+    config::HistoryConfig,
+    data_source::{DataSource, SourceResponse},
+    error::BadgifyError,
+    query::Query,
+    utils::unix_now,
+};
+
+/// A result of a tracked query at some point in time.
+#[derive(Debug, Serialize)]
+pub struct HistoryPoint {
+    pub timestamp: u64,
+    pub value: SourceResponse,
+}
+
+/// Records the results of configured queries on a schedule in a local SQLite
+/// database, to show how they evolved.
+pub struct HistoryStore {
+    store: Mutex<Connection>,
+    tracked: Vec<String>,
+    config: HistoryConfig,
+}
+
+impl HistoryStore {
+    pub fn open(config: HistoryConfig) -> Result<Self, BadgifyError> {
+        let connection = Connection::open(&config.database_path)?;
+
+        connection.execute_batch(
+            "CREATE TABLE IF NOT EXISTS samples (
+                query TEXT NOT NULL,
+                timestamp INTEGER NOT NULL,
+                result TEXT NOT NULL,
+                PRIMARY KEY (query, timestamp)
+            );",
+        )?;
+
+        // Tracked queries are compared by their parsed form, so that e.g. the
+        // case of an address does not matter.
+        let tracked = config
+            .queries
+            .iter()
+            .map(|path| Ok(Query::parse_path(path)?.storage_key()))
+            .collect::<Result<_, BadgifyError>>()?;
+
+        Ok(Self {
+            store: Mutex::new(connection),
+            tracked,
+            config,
+        })
+    }
+
+    pub fn is_tracked(&self, query: &Query) -> bool {
+        self.tracked.contains(&query.storage_key())
+    }
+
+    /// Runs `f` on the blocking thread pool, so that SQLite work and waiting
+    /// for the store do not stall the async workers serving requests.
+    pub async fn blocking<T, F>(self: &Arc<Self>, f: F) -> Result<T, BadgifyError>
+    where
+        F: FnOnce(&HistoryStore) -> Result<T, BadgifyError> + Send + 'static,
+        T: Send + 'static,
+    {
+        let history = self.clone();
+
+        tokio::task::spawn_blocking(move || f(&history))
+            .await
+            .map_err(|err| BadgifyError::Internal(format!("History task failed: {err}")))?
+    }
+
+    pub fn record(
+        &self,
+        query: &Query,
+        timestamp: u64,
+        value: &SourceResponse,
+    ) -> Result<(), BadgifyError> {
+        self.store.lock().unwrap().execute(
+            "INSERT OR REPLACE INTO samples (query, timestamp, result) VALUES (?1, ?2, ?3)",
+            params![
+                query.storage_key(),
+                timestamp,
+                serde_json::to_string(value)?
+            ],
+        )?;
+
+        Ok(())
+    }
+
+    /// Returns the results of `query` recorded between `from` and `to`
+    /// (inclusive), keeping the last one of every `interval` seconds.
+    pub fn series(
+        &self,
+        query: &Query,
+        from: u64,
+        to: u64,
+        interval: u64,
+    ) -> Result<Vec<HistoryPoint>, BadgifyError> {
+        let store = self.store.lock().unwrap();
+
+        // SQLite returns the other columns of the row holding the MAX().
+        let mut statement = store.prepare(
+            "SELECT MAX(timestamp), result FROM samples
+            WHERE query = ?1 AND timestamp BETWEEN ?2 AND ?3
+            GROUP BY timestamp / ?4
+            ORDER BY timestamp",
+        )?;
+
+        let rows = statement
+            .query_map(
+                params![query.storage_key(), from, to, interval.max(1)],
+                |row| Ok((row.get::<_, u64>(0)?, row.get::<_, String>(1)?)),
+            )?
+            .collect::<Result<Vec<_>, _>>()?;
+
+        rows.into_iter()
+            .map(|(timestamp, result)| {
+                Ok(HistoryPoint {
+                    timestamp,
+                    value: serde_json::from_str(&result)?,
+                })
+            })
+            .collect()
+    }
+
+    /// Records every tracked query forever, once per configured interval.
+    pub async fn run(self: Arc<Self>, data_source: Arc<DataSource>) {
+        let interval = Duration::from_secs(self.config.interval_secs.max(1));
+
+        loop {
+            for path in &self.config.queries {
+                if let Err(err) = self.record_query(&data_source, path).await {
+                    println!("Failed to record history of {path}: {err}");
+                }
+            }
+
+            tokio::time::sleep(interval).await;
+        }
+    }
+
+    async fn record_query(
+        self: &Arc<Self>,
+        data_source: &DataSource,
+        path: &str,
+    ) -> Result<(), BadgifyError> {
+        let query = Query::parse_path(path)?;
+        let result = data_source.get_data(query.clone()).await?;
+
+        // A stale result would repeat an earlier value at a later time.
+        if result.stale {
+            return Ok(());
+        }
+
+        let timestamp = unix_now();
+        self.blocking(move |history| history.record(&query, timestamp, &result.data.result))
+            .await
+    }
+}
@@ -1,38 +1,64 @@
 pub mod badge;
+pub mod config;
 pub mod data_source;
+pub mod error;
+pub mod evm_chain_icons;
+pub mod evm_chainlist;
+pub mod history;
 pub mod query;
 pub mod types;
 pub mod utils;
 
-mod evm_chainlist;
-mod services;
+pub mod services;
 
-use std::{error::Error, path::PathBuf};
+use std::{error::Error, path::PathBuf, sync::Arc, time::Duration};
+
+use error::BadgifyError;
 
 use actix_web::{middleware::Logger, web, App, HttpServer};
-use data_source::{DataSource, SourceResponseWithMetadata};
+use config::Config;
+use data_source::{DataSource, QueryResult};
+use history::HistoryStore;
 use query::Query;
 
 struct Executor {
-    data_source: DataSource,
+    data_source: Arc<DataSource>,
+    history: Option<Arc<HistoryStore>>,
+    admin_token: Option<String>,
 }
 
 impl Executor {
-    pub fn new() -> Self {
-        Self {
-            data_source: DataSource::default(),
-        }
+    /// Builds the data sources and starts recording the history of tracked queries.
+    pub fn new(config: &Config) -> Result<Self, Box<dyn Error>> {
+        let data_source = Arc::new(DataSource::new(config)?);
+
+        let history = if config.history.enabled {
+            let history = Arc::new(HistoryStore::open(config.history.clone())?);
+            actix_web::rt::spawn(history.clone().run(data_source.clone()));
+            Some(history)
+        } else {
+            None
+        };
+
+        Ok(Self {
+            data_source,
+            history,
+            admin_token: config.admin.token.clone(),
+        })
     }
 
-    pub async fn query_data(
-        &self,
-        path: &str,
-    ) -> Result<SourceResponseWithMetadata, Box<dyn Error>> {
+    pub async fn query_data(&self, path: &str) -> Result<QueryResult, BadgifyError> {
         let query = Query::parse_path(path)?;
         self.data_source.get_data(query).await
     }
 
-    pub async fn get_scanner_link(&self, path: &str) -> Result<String, Box<dyn Error>> {
+    /// How long results of the query at `path` may be cached for.
+    pub fn cache_ttl(&self, path: &str) -> Result<Duration, BadgifyError> {
+        let query = Query::parse_path(path)?;
+        Ok(self.data_source.cache_ttl(&query))
+    }
+
+    pub async fn get_scanner_link(&self, path: &str) -> Result<String, BadgifyError> {
         let query = Query::parse_path(path)?;
         self.data_source.get_scanner_link(query).await
     }
@@ -45,7 +71,8 @@ pub async fn start_server(host: &str, port: u16) {
     // Initialize logger
     env_logger::init_from_env(env_logger::Env::new().default_filter_or("info"));
 
-    let executor = web::Data::new(Executor::new());
+    let config = Config::load().expect("Cannot load configuration");
+    let executor = web::Data::new(Executor::new(&config).expect("Cannot initialize data sources"));
 
     // Start HTTP Server
     HttpServer::new(move || {
@@ -54,6 +81,11 @@ pub async fn start_server(host: &str, port: u16) {
             .app_data(executor.clone())
             .service(services::api::health)
             .service(services::api::query)
+            .service(services::api::validate)
+            .service(services::history::history)
+            .service(services::chains::chains)
+            .service(services::chains::chain_by_id)
+            .service(services::admin::rpc_health)
             .service(services::badge::badge_data_api)
             .service(services::badge::badge_image)
             .service(services::scanner::scanner)
@@ -1,25 +1,56 @@
-use std::str::Split;
+use std::fmt::Display;
 
-use serde::Serialize;
+use serde::{Deserialize, Serialize};
+
+use super::{QueryParseError, Segments};
+
+const EXPECTED_NETWORK: &str = "mainnet, testnet or signet";
+const EXPECTED_TYPE: &str = "balance or tx_count";
+const EXPECTED_ADDRESS: &str = "a base58 or bech32 Bitcoin address";
 
 type BitcoinAddress = String;
 
-#[derive(Debug)]
+#[derive(Debug, Clone)]
 pub enum BitcoinQuery {
     NativeBalance {
         network: BitcoinNetwork,
         address: BitcoinAddress,
     },
+    TxCount {
+        network: BitcoinNetwork,
+        address: BitcoinAddress,
+    },
 }
 
-#[derive(Debug, Serialize)]
+#[derive(Debug, Serialize, Deserialize, Clone, Copy)]
 pub enum BitcoinNetwork {
     Mainnet,
     Testnet,
     Signet,
 }
 
-#[derive(Debug)]
+impl BitcoinNetwork {
+    pub const ALL: [BitcoinNetwork; 3] = [Self::Mainnet, Self::Testnet, Self::Signet];
+}
+
+/// Formats the query as its normalized path, without the `btc/` prefix.
+impl Display for BitcoinQuery {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let (network, kind, address) = match self {
+            Self::NativeBalance { network, address } => (network, "balance", address),
+            Self::TxCount { network, address } => (network, "tx_count", address),
+        };
+        let network = match network {
+            BitcoinNetwork::Mainnet => "mainnet",
+            BitcoinNetwork::Testnet => "testnet",
+            BitcoinNetwork::Signet => "signet",
+        };
+
+        write!(f, "{network}/{kind}/{address}")
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
 pub enum BitcoinQueryParseError {
     BadNetwork,
     BadType,
@@ -27,37 +58,61 @@ pub enum BitcoinQueryParseError {
 }
 
 impl BitcoinQuery {
-    pub fn parse(mut path_params: Split<'_, &str>) -> Result<Self, BitcoinQueryParseError> {
-        let network = path_params
-            .next()
-            .map(|network| match network.to_lowercase().as_str() {
-                "mainnet" => Ok(BitcoinNetwork::Mainnet),
-                "testnet" => Ok(BitcoinNetwork::Testnet),
-                "signet" => Ok(BitcoinNetwork::Signet),
-                _ => Err(BitcoinQueryParseError::BadNetwork),
-            })
-            .unwrap_or(Err(BitcoinQueryParseError::BadNetwork))?;
+    /// The query type as written in the path, prefixed with `btc_`.
+    pub fn kind(&self) -> &'static str {
+        match self {
+            Self::NativeBalance { .. } => "btc_balance",
+            Self::TxCount { .. } => "btc_tx_count",
+        }
+    }
+
+    pub fn parse(mut path_params: Segments<'_>) -> Result<Self, QueryParseError> {
+        let network = match path_params.next().map(str::to_lowercase).as_deref() {
+            Some("mainnet") => BitcoinNetwork::Mainnet,
+            Some("testnet") => BitcoinNetwork::Testnet,
+            Some("signet") => BitcoinNetwork::Signet,
+            _ => {
+                return Err(path_params.error(BitcoinQueryParseError::BadNetwork, EXPECTED_NETWORK))
+            }
+        };
 
         match path_params
             .next()
-            .ok_or(BitcoinQueryParseError::BadType)?
+            .ok_or_else(|| path_params.error(BitcoinQueryParseError::BadType, EXPECTED_TYPE))?
             .to_lowercase()
             .as_ref()
         {
             "balance" => Ok(Self::parse_native_balance(path_params, network)?),
-            _ => Err(BitcoinQueryParseError::BadType),
+            "tx_count" => Ok(Self::parse_tx_count(path_params, network)?),
+            _ => Err(path_params.error(BitcoinQueryParseError::BadType, EXPECTED_TYPE)),
         }
     }
 
     fn parse_native_balance(
-        mut path_params: Split<'_, &str>,
+        mut path_params: Segments<'_>,
         network: BitcoinNetwork,
-    ) -> Result<Self, BitcoinQueryParseError> {
-        let address = path_params
-            .next()
-            .map(|s| s.to_string())
-            .ok_or(BitcoinQueryParseError::BadNetwork)?;
+    ) -> Result<Self, QueryParseError> {
+        let address = parse_address(&mut path_params)?;
 
         Ok(Self::NativeBalance { network, address })
     }
+
+    fn parse_tx_count(
+        mut path_params: Segments<'_>,
+        network: BitcoinNetwork,
+    ) -> Result<Self, QueryParseError> {
+        let address = parse_address(&mut path_params)?;
+
+        Ok(Self::TxCount { network, address })
+    }
+}
+
+/// Checks the address for characters that are never part of a Bitcoin
+/// address; whether it exists is left to the data source.
+fn parse_address(path_params: &mut Segments<'_>) -> Result<BitcoinAddress, QueryParseError> {
+    path_params
+        .next()
+        .filter(|address| !address.is_empty() && address.chars().all(|c| c.is_ascii_alphanumeric()))
+        .map(str::to_string)
+        .ok_or_else(|| path_params.error(BitcoinQueryParseError::BadAddress, EXPECTED_ADDRESS))
 }
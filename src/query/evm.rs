@@ -1,8 +1,26 @@
 use crate::types::{ChainID, EvmAddress};
 use crate::utils::{EvmAddressInteratorExt, Uint256IteratorExt};
-use std::str::Split;
+use alloy::primitives::U256;
+use serde::{Deserialize, Serialize};
+use std::{fmt::Display, str::FromStr};
 
-#[derive(Debug)]
+use super::{QueryParseError, Segments};
+
+const EXPECTED_CHAIN_ID: &str = "a numeric chain ID, e.g. 1";
+const EXPECTED_TYPE: &str = "one of balance, erc20_balance, safe, ens_expiry, proxy, owner, \
+     storage, code_size, is_contract, block_number, block_age, gas_price, base_fee, \
+     priority_fee, tx_count, erc20_flow or erc20_holders";
+const EXPECTED_ADDRESS: &str = "a 0x-prefixed 20-byte hex address";
+const EXPECTED_NAME: &str = "a second-level .eth name, e.g. vitalik.eth";
+const EXPECTED_SLOT: &str = "a storage slot as a decimal or 0x-prefixed hex number";
+const EXPECTED_WINDOW: &str =
+    "a number of blocks, hours or days up to 500000b or 90d, e.g. 5000b, 24h or 30d";
+
+/// Largest window of `erc20_flow` queries, which scan every block in it.
+pub const MAX_WINDOW_BLOCKS: u64 = 500_000;
+pub const MAX_WINDOW_SECONDS: u64 = 90 * 24 * 60 * 60;
+
+#[derive(Debug, Clone)]
 pub enum EvmQuery {
     NativeBalance {
         chain_id: ChainID,
@@ -13,55 +31,380 @@ pub enum EvmQuery {
         address: EvmAddress,
         contract_address: EvmAddress,
     },
+    Safe {
+        chain_id: ChainID,
+        safe_address: EvmAddress,
+        field: SafeField,
+    },
+    EnsExpiry {
+        chain_id: ChainID,
+        name: String,
+    },
+    Proxy {
+        chain_id: ChainID,
+        proxy_address: EvmAddress,
+        slot: ProxySlot,
+    },
+    Owner {
+        chain_id: ChainID,
+        contract_address: EvmAddress,
+    },
+    Storage {
+        chain_id: ChainID,
+        contract_address: EvmAddress,
+        slot: U256,
+        kind: StorageKind,
+    },
+    CodeSize {
+        chain_id: ChainID,
+        address: EvmAddress,
+    },
+    IsContract {
+        chain_id: ChainID,
+        address: EvmAddress,
+    },
+    ChainStatus {
+        chain_id: ChainID,
+        metric: ChainMetric,
+    },
+    TxCount {
+        chain_id: ChainID,
+        address: EvmAddress,
+        pending: bool,
+    },
+    ERC20Flow {
+        chain_id: ChainID,
+        address: EvmAddress,
+        contract_address: EvmAddress,
+        metric: FlowMetric,
+        window: BlockWindow,
+    },
+    ERC20Holders {
+        chain_id: ChainID,
+        contract_address: EvmAddress,
+    },
+}
+
+/// The part of a Safe multisig configuration a badge shows.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum SafeField {
+    Threshold,
+    Owners,
+    Nonce,
 }
 
-#[derive(Debug)]
+/// The EIP-1967 storage slot a proxy badge reads.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ProxySlot {
+    Implementation,
+    Admin,
+}
+
+/// How the 32-byte word read from a storage slot is decoded.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum StorageKind {
+    #[default]
+    Uint,
+    Address,
+    Bytes,
+}
+
+/// A network-wide metric that needs no address.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ChainMetric {
+    BlockNumber,
+    BlockAge,
+    GasPrice,
+    BaseFee,
+    PriorityFee,
+}
+
+/// What is reported from the `Transfer` events of a token for an address.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum FlowMetric {
+    Inflow,
+    Outflow,
+    Net,
+    Count,
+}
+
+/// How far back from the latest block event logs are scanned.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum BlockWindow {
+    Blocks(u64),
+    Seconds(u64),
+}
+
+impl Default for BlockWindow {
+    fn default() -> Self {
+        Self::Seconds(30 * 24 * 60 * 60)
+    }
+}
+
+impl FromStr for BlockWindow {
+    type Err = EvmQueryParseError;
+
+    fn from_str(window: &str) -> Result<Self, Self::Err> {
+        let window = window.to_lowercase();
+        let split_at = window
+            .find(|c: char| !c.is_ascii_digit())
+            .ok_or(EvmQueryParseError::BadWindow)?;
+        let (amount, unit) = window.split_at(split_at);
+        let amount: u64 = amount.parse().map_err(|_| EvmQueryParseError::BadWindow)?;
+
+        let unit_seconds = match unit {
+            "b" if amount <= MAX_WINDOW_BLOCKS => return Ok(Self::Blocks(amount)),
+            "h" => 60 * 60,
+            "d" => 24 * 60 * 60,
+            _ => return Err(EvmQueryParseError::BadWindow),
+        };
+
+        amount
+            .checked_mul(unit_seconds)
+            .filter(|seconds| *seconds <= MAX_WINDOW_SECONDS)
+            .map(Self::Seconds)
+            .ok_or(EvmQueryParseError::BadWindow)
+    }
+}
+
+impl Display for BlockWindow {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::Blocks(blocks) => write!(f, "{blocks}b"),
+            Self::Seconds(seconds) if seconds % (24 * 60 * 60) == 0 => {
+                write!(f, "{}d", seconds / (24 * 60 * 60))
+            }
+            Self::Seconds(seconds) => write!(f, "{}h", seconds / (60 * 60)),
+        }
+    }
+}
+
+impl Serialize for BlockWindow {
+    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: serde::Serializer,
+    {
+        serializer.collect_str(self)
+    }
+}
+
+impl<'de> Deserialize<'de> for BlockWindow {
+    fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
+    where
+        D: serde::Deserializer<'de>,
+    {
+        String::deserialize(deserializer)?
+            .parse()
+            .map_err(|_| serde::de::Error::custom("invalid block window"))
+    }
+}
+
+/// Formats the query as its normalized path, without the `evm/` prefix.
+impl Display for EvmQuery {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let (chain_id, kind) = (self.chain_id(), self.kind());
+
+        match self {
+            Self::NativeBalance { address, .. }
+            | Self::CodeSize { address, .. }
+            | Self::IsContract { address, .. } => write!(f, "{chain_id}/{kind}/{address:#x}"),
+            Self::ERC20Balance {
+                address,
+                contract_address,
+                ..
+            } => write!(f, "{chain_id}/{kind}/{contract_address:#x}/{address:#x}"),
+            Self::Safe {
+                safe_address,
+                field,
+                ..
+            } => {
+                let field = match field {
+                    SafeField::Threshold => "threshold",
+                    SafeField::Owners => "owners",
+                    SafeField::Nonce => "nonce",
+                };
+                write!(f, "{chain_id}/{kind}/{safe_address:#x}/{field}")
+            }
+            Self::EnsExpiry { name, .. } => write!(f, "{chain_id}/{kind}/{name}"),
+            Self::Proxy {
+                proxy_address,
+                slot,
+                ..
+            } => {
+                let slot = match slot {
+                    ProxySlot::Implementation => "implementation",
+                    ProxySlot::Admin => "admin",
+                };
+                write!(f, "{chain_id}/{kind}/{proxy_address:#x}/{slot}")
+            }
+            Self::Owner {
+                contract_address, ..
+            }
+            | Self::ERC20Holders {
+                contract_address, ..
+            } => write!(f, "{chain_id}/{kind}/{contract_address:#x}"),
+            Self::Storage {
+                contract_address,
+                slot,
+                kind: storage_kind,
+                ..
+            } => {
+                let storage_kind = match storage_kind {
+                    StorageKind::Uint => "uint",
+                    StorageKind::Address => "address",
+                    StorageKind::Bytes => "bytes",
+                };
+                write!(
+                    f,
+                    "{chain_id}/{kind}/{contract_address:#x}/{slot:#x}/{storage_kind}"
+                )
+            }
+            Self::ChainStatus { .. } => write!(f, "{chain_id}/{kind}"),
+            Self::TxCount {
+                address, pending, ..
+            } => {
+                let block = if *pending { "pending" } else { "latest" };
+                write!(f, "{chain_id}/{kind}/{address:#x}/{block}")
+            }
+            Self::ERC20Flow {
+                address,
+                contract_address,
+                metric,
+                window,
+                ..
+            } => {
+                let metric = match metric {
+                    FlowMetric::Inflow => "inflow",
+                    FlowMetric::Outflow => "outflow",
+                    FlowMetric::Net => "net",
+                    FlowMetric::Count => "count",
+                };
+                write!(
+                    f,
+                    "{chain_id}/{kind}/{contract_address:#x}/{address:#x}/{metric}/{window}"
+                )
+            }
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
 pub enum EvmQueryParseError {
     BadChainID,
     BadType,
     BadAddress,
+    BadName,
+    BadSlot,
+    BadWindow,
 }
 
 impl EvmQuery {
-    pub fn parse(mut path_params: Split<'_, &str>) -> Result<EvmQuery, EvmQueryParseError> {
+    pub fn chain_id(&self) -> ChainID {
+        match self {
+            Self::NativeBalance { chain_id, .. }
+            | Self::ERC20Balance { chain_id, .. }
+            | Self::Safe { chain_id, .. }
+            | Self::EnsExpiry { chain_id, .. }
+            | Self::Proxy { chain_id, .. }
+            | Self::Owner { chain_id, .. }
+            | Self::Storage { chain_id, .. }
+            | Self::CodeSize { chain_id, .. }
+            | Self::IsContract { chain_id, .. }
+            | Self::ChainStatus { chain_id, .. }
+            | Self::TxCount { chain_id, .. }
+            | Self::ERC20Flow { chain_id, .. }
+            | Self::ERC20Holders { chain_id, .. } => *chain_id,
+        }
+    }
+
+    /// The query type as written in the path, e.g. `erc20_balance`.
+    pub fn kind(&self) -> &'static str {
+        match self {
+            Self::NativeBalance { .. } => "balance",
+            Self::ERC20Balance { .. } => "erc20_balance",
+            Self::Safe { .. } => "safe",
+            Self::EnsExpiry { .. } => "ens_expiry",
+            Self::Proxy { .. } => "proxy",
+            Self::Owner { .. } => "owner",
+            Self::Storage { .. } => "storage",
+            Self::CodeSize { .. } => "code_size",
+            Self::IsContract { .. } => "is_contract",
+            Self::ChainStatus { metric, .. } => match metric {
+                ChainMetric::BlockNumber => "block_number",
+                ChainMetric::BlockAge => "block_age",
+                ChainMetric::GasPrice => "gas_price",
+                ChainMetric::BaseFee => "base_fee",
+                ChainMetric::PriorityFee => "priority_fee",
+            },
+            Self::TxCount { .. } => "tx_count",
+            Self::ERC20Flow { .. } => "erc20_flow",
+            Self::ERC20Holders { .. } => "erc20_holders",
+        }
+    }
+
+    pub fn parse(mut path_params: Segments<'_>) -> Result<EvmQuery, QueryParseError> {
         let chain_id = path_params
             .next_uint256()
-            .map_err(|_| EvmQueryParseError::BadChainID)?;
+            .map_err(|_| path_params.error(EvmQueryParseError::BadChainID, EXPECTED_CHAIN_ID))?;
 
         match path_params
             .next()
-            .ok_or(EvmQueryParseError::BadType)?
+            .ok_or_else(|| path_params.error(EvmQueryParseError::BadType, EXPECTED_TYPE))?
             .to_lowercase()
             .as_ref()
         {
             "balance" => Ok(Self::parse_native_balance(path_params, chain_id)?),
             "erc20_balance" => Ok(Self::parse_erc20_balance(path_params, chain_id)?),
-            _ => Err(EvmQueryParseError::BadType),
+            "safe" => Ok(Self::parse_safe(path_params, chain_id)?),
+            "ens_expiry" => Ok(Self::parse_ens_expiry(path_params, chain_id)?),
+            "proxy" => Ok(Self::parse_proxy(path_params, chain_id)?),
+            "owner" => Ok(Self::parse_owner(path_params, chain_id)?),
+            "storage" => Ok(Self::parse_storage(path_params, chain_id)?),
+            "code_size" => Ok(Self::parse_code_size(path_params, chain_id)?),
+            "is_contract" => Ok(Self::parse_is_contract(path_params, chain_id)?),
+            "block_number" => Ok(Self::chain_status(chain_id, ChainMetric::BlockNumber)),
+            "block_age" => Ok(Self::chain_status(chain_id, ChainMetric::BlockAge)),
+            "gas_price" => Ok(Self::chain_status(chain_id, ChainMetric::GasPrice)),
+            "base_fee" => Ok(Self::chain_status(chain_id, ChainMetric::BaseFee)),
+            "priority_fee" => Ok(Self::chain_status(chain_id, ChainMetric::PriorityFee)),
+            "tx_count" => Ok(Self::parse_tx_count(path_params, chain_id)?),
+            "erc20_flow" => Ok(Self::parse_erc20_flow(path_params, chain_id)?),
+            "erc20_holders" => Ok(Self::parse_erc20_holders(path_params, chain_id)?),
+            _ => Err(path_params.error(EvmQueryParseError::BadType, EXPECTED_TYPE)),
         }
     }
 
+    fn chain_status(chain_id: ChainID, metric: ChainMetric) -> EvmQuery {
+        EvmQuery::ChainStatus { chain_id, metric }
+    }
+
     fn parse_native_balance(
-        mut path_params: Split<'_, &str>,
+        mut path_params: Segments<'_>,
         chain_id: ChainID,
-    ) -> Result<EvmQuery, EvmQueryParseError> {
+    ) -> Result<EvmQuery, QueryParseError> {
         let address = path_params
             .next_evm_address()
-            .map_err(|_| EvmQueryParseError::BadAddress)?;
+            .map_err(|_| path_params.error(EvmQueryParseError::BadAddress, EXPECTED_ADDRESS))?;
 
         Ok(EvmQuery::NativeBalance { chain_id, address })
     }
 
     fn parse_erc20_balance(
-        mut path_params: Split<'_, &str>,
+        mut path_params: Segments<'_>,
         chain_id: ChainID,
-    ) -> Result<EvmQuery, EvmQueryParseError> {
+    ) -> Result<EvmQuery, QueryParseError> {
         let contract_address = path_params
             .next_evm_address()
-            .map_err(|_| EvmQueryParseError::BadAddress)?;
+            .map_err(|_| path_params.error(EvmQueryParseError::BadAddress, EXPECTED_ADDRESS))?;
 
         let address = path_params
             .next_evm_address()
-            .map_err(|_| EvmQueryParseError::BadAddress)?;
+            .map_err(|_| path_params.error(EvmQueryParseError::BadAddress, EXPECTED_ADDRESS))?;
 
         Ok(EvmQuery::ERC20Balance {
             chain_id,
@@ -69,4 +412,244 @@ impl EvmQuery {
             contract_address,
         })
     }
+
+    fn parse_safe(
+        mut path_params: Segments<'_>,
+        chain_id: ChainID,
+    ) -> Result<EvmQuery, QueryParseError> {
+        let safe_address = path_params
+            .next_evm_address()
+            .map_err(|_| path_params.error(EvmQueryParseError::BadAddress, EXPECTED_ADDRESS))?;
+
+        let field = match path_params
+            .next()
+            .ok_or_else(|| {
+                path_params.error(EvmQueryParseError::BadType, "threshold, owners or nonce")
+            })?
+            .to_lowercase()
+            .as_ref()
+        {
+            "threshold" => SafeField::Threshold,
+            "owners" => SafeField::Owners,
+            "nonce" => SafeField::Nonce,
+            _ => {
+                return Err(
+                    path_params.error(EvmQueryParseError::BadType, "threshold, owners or nonce")
+                )
+            }
+        };
+
+        Ok(EvmQuery::Safe {
+            chain_id,
+            safe_address,
+            field,
+        })
+    }
+
+    fn parse_ens_expiry(
+        mut path_params: Segments<'_>,
+        chain_id: ChainID,
+    ) -> Result<EvmQuery, QueryParseError> {
+        let name = path_params
+            .next()
+            .ok_or_else(|| path_params.error(EvmQueryParseError::BadName, EXPECTED_NAME))?
+            .to_lowercase();
+
+        // The .eth registrar only tracks expiry of second-level names.
+        match name.strip_suffix(".eth") {
+            Some(label) if !label.is_empty() && !label.contains('.') => {
+                Ok(EvmQuery::EnsExpiry { chain_id, name })
+            }
+            _ => Err(path_params.error(EvmQueryParseError::BadName, EXPECTED_NAME)),
+        }
+    }
+
+    fn parse_proxy(
+        mut path_params: Segments<'_>,
+        chain_id: ChainID,
+    ) -> Result<EvmQuery, QueryParseError> {
+        let proxy_address = path_params
+            .next_evm_address()
+            .map_err(|_| path_params.error(EvmQueryParseError::BadAddress, EXPECTED_ADDRESS))?;
+
+        let slot = match path_params
+            .next()
+            .ok_or_else(|| {
+                path_params.error(EvmQueryParseError::BadType, "implementation or admin")
+            })?
+            .to_lowercase()
+            .as_ref()
+        {
+            "implementation" => ProxySlot::Implementation,
+            "admin" => ProxySlot::Admin,
+            _ => {
+                return Err(
+                    path_params.error(EvmQueryParseError::BadType, "implementation or admin")
+                )
+            }
+        };
+
+        Ok(EvmQuery::Proxy {
+            chain_id,
+            proxy_address,
+            slot,
+        })
+    }
+
+    fn parse_owner(
+        mut path_params: Segments<'_>,
+        chain_id: ChainID,
+    ) -> Result<EvmQuery, QueryParseError> {
+        let contract_address = path_params
+            .next_evm_address()
+            .map_err(|_| path_params.error(EvmQueryParseError::BadAddress, EXPECTED_ADDRESS))?;
+
+        Ok(EvmQuery::Owner {
+            chain_id,
+            contract_address,
+        })
+    }
+
+    fn parse_storage(
+        mut path_params: Segments<'_>,
+        chain_id: ChainID,
+    ) -> Result<EvmQuery, QueryParseError> {
+        let contract_address = path_params
+            .next_evm_address()
+            .map_err(|_| path_params.error(EvmQueryParseError::BadAddress, EXPECTED_ADDRESS))?;
+
+        let slot = path_params
+            .next_uint256()
+            .map_err(|_| path_params.error(EvmQueryParseError::BadSlot, EXPECTED_SLOT))?;
+
+        let kind = match path_params
+            .next()
+            .map(|kind| kind.to_lowercase())
+            .as_deref()
+        {
+            None | Some("uint") => StorageKind::Uint,
+            Some("address") => StorageKind::Address,
+            Some("bytes") => StorageKind::Bytes,
+            Some(_) => {
+                return Err(path_params.error(EvmQueryParseError::BadType, "uint, address or bytes"))
+            }
+        };
+
+        Ok(EvmQuery::Storage {
+            chain_id,
+            contract_address,
+            slot,
+            kind,
+        })
+    }
+
+    fn parse_code_size(
+        mut path_params: Segments<'_>,
+        chain_id: ChainID,
+    ) -> Result<EvmQuery, QueryParseError> {
+        let address = path_params
+            .next_evm_address()
+            .map_err(|_| path_params.error(EvmQueryParseError::BadAddress, EXPECTED_ADDRESS))?;
+
+        Ok(EvmQuery::CodeSize { chain_id, address })
+    }
+
+    fn parse_is_contract(
+        mut path_params: Segments<'_>,
+        chain_id: ChainID,
+    ) -> Result<EvmQuery, QueryParseError> {
+        let address = path_params
+            .next_evm_address()
+            .map_err(|_| path_params.error(EvmQueryParseError::BadAddress, EXPECTED_ADDRESS))?;
+
+        Ok(EvmQuery::IsContract { chain_id, address })
+    }
+
+    fn parse_tx_count(
+        mut path_params: Segments<'_>,
+        chain_id: ChainID,
+    ) -> Result<EvmQuery, QueryParseError> {
+        let address = path_params
+            .next_evm_address()
+            .map_err(|_| path_params.error(EvmQueryParseError::BadAddress, EXPECTED_ADDRESS))?;
+
+        let pending = match path_params
+            .next()
+            .map(|block| block.to_lowercase())
+            .as_deref()
+        {
+            None | Some("latest") => false,
+            Some("pending") => true,
+            Some(_) => {
+                return Err(path_params.error(EvmQueryParseError::BadType, "latest or pending"))
+            }
+        };
+
+        Ok(EvmQuery::TxCount {
+            chain_id,
+            address,
+            pending,
+        })
+    }
+
+    fn parse_erc20_flow(
+        mut path_params: Segments<'_>,
+        chain_id: ChainID,
+    ) -> Result<EvmQuery, QueryParseError> {
+        let contract_address = path_params
+            .next_evm_address()
+            .map_err(|_| path_params.error(EvmQueryParseError::BadAddress, EXPECTED_ADDRESS))?;
+
+        let address = path_params
+            .next_evm_address()
+            .map_err(|_| path_params.error(EvmQueryParseError::BadAddress, EXPECTED_ADDRESS))?;
+
+        let metric = match path_params
+            .next()
+            .ok_or_else(|| {
+                path_params.error(EvmQueryParseError::BadType, "inflow, outflow, net or count")
+            })?
+            .to_lowercase()
+            .as_ref()
+        {
+            "inflow" => FlowMetric::Inflow,
+            "outflow" => FlowMetric::Outflow,
+            "net" => FlowMetric::Net,
+            "count" => FlowMetric::Count,
+            _ => {
+                return Err(
+                    path_params.error(EvmQueryParseError::BadType, "inflow, outflow, net or count")
+                )
+            }
+        };
+
+        let window = match path_params.next() {
+            Some(window) => window
+                .parse()
+                .map_err(|kind| path_params.error(kind, EXPECTED_WINDOW))?,
+            None => BlockWindow::default(),
+        };
+
+        Ok(EvmQuery::ERC20Flow {
+            chain_id,
+            address,
+            contract_address,
+            metric,
+            window,
+        })
+    }
+
+    fn parse_erc20_holders(
+        mut path_params: Segments<'_>,
+        chain_id: ChainID,
+    ) -> Result<EvmQuery, QueryParseError> {
+        let contract_address = path_params
+            .next_evm_address()
+            .map_err(|_| path_params.error(EvmQueryParseError::BadAddress, EXPECTED_ADDRESS))?;
+
+        Ok(EvmQuery::ERC20Holders {
+            chain_id,
+            contract_address,
+        })
+    }
 }
@@ -1,52 +1,165 @@
 pub mod bitcoin;
 pub mod evm;
+mod segments;
 
-pub use evm::{EvmQuery, EvmQueryParseError};
+pub use evm::{
+    BlockWindow, ChainMetric, EvmQuery, EvmQueryParseError, FlowMetric, ProxySlot, SafeField,
+    StorageKind,
+};
+pub use segments::Segments;
+
+use std::fmt::Display;
 
 use bitcoin::{BitcoinQuery, BitcoinQueryParseError};
+use serde::Serialize;
 
-#[derive(Debug)]
+#[derive(Debug, Clone)]
 pub enum Query {
     Evm(EvmQuery),
     Bitcoin(BitcoinQuery),
 }
 
-#[derive(Debug)]
-pub enum QueryParseError {
-    SourceNotFoundError,
-    InvalidSource(String),
-    EvmQueryParseError(EvmQueryParseError),
-    BitcoinQueryParseError(BitcoinQueryParseError),
+/// Error in one segment of a query path, e.g. segment 3 of
+/// `evm/1/balance/0xabc` for a malformed address.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct QueryParseError {
+    #[serde(rename = "error")]
+    pub kind: QueryParseErrorKind,
+    /// Index of the offending segment in the `/`-separated path.
+    pub segment: usize,
+    /// The offending segment, or `None` if the path ends before it.
+    pub text: Option<String>,
+    /// What the segment should look like.
+    pub expected: String,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum QueryParseErrorKind {
+    InvalidSource,
+    Evm(EvmQueryParseError),
+    Bitcoin(BitcoinQueryParseError),
 }
 
 impl Query {
+    pub fn kind(&self) -> &'static str {
+        match self {
+            Self::Evm(evm_query) => evm_query.kind(),
+            Self::Bitcoin(bitcoin_query) => bitcoin_query.kind(),
+        }
+    }
+
+    /// Key under which results are cached in memory. Built from the parsed
+    /// query, so that equivalent paths, e.g. with differently cased
+    /// addresses, share it.
+    pub fn cache_key(&self) -> String {
+        format!("{self:?}")
+    }
+
+    /// Key under which results are persisted: the normalized path, which
+    /// unlike the cache key does not change with the internal representation.
+    pub fn storage_key(&self) -> String {
+        self.to_string()
+    }
+
     pub fn parse_path(path: &str) -> Result<Self, QueryParseError> {
-        let mut path_params = path.split("/");
-        let source_type = path_params
-            .next()
-            .ok_or(QueryParseError::SourceNotFoundError)?;
+        let mut segments = Segments::new(path);
+        let source_type = segments.next().unwrap_or_default();
 
         match source_type.to_lowercase().as_str() {
-            "evm" => Ok(Query::Evm(
-                EvmQuery::parse(path_params).map_err(QueryParseError::EvmQueryParseError)?,
-            )),
-            "btc" => Ok(Query::Bitcoin(
-                BitcoinQuery::parse(path_params)
-                    .map_err(QueryParseError::BitcoinQueryParseError)?,
-            )),
-            invalid => Err(QueryParseError::InvalidSource(invalid.into())),
+            "evm" => Ok(Query::Evm(EvmQuery::parse(segments)?)),
+            "btc" => Ok(Query::Bitcoin(BitcoinQuery::parse(segments)?)),
+            _ => Err(segments.error(QueryParseErrorKind::InvalidSource, "evm or btc")),
         }
     }
 }
 
-impl std::fmt::Display for QueryParseError {
+/// Formats the query as its normalized path, which parses back to it.
+impl Display for Query {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
-        write!(f, "QueryParseError")
+        match self {
+            Self::Evm(evm_query) => write!(f, "evm/{evm_query}"),
+            Self::Bitcoin(bitcoin_query) => write!(f, "btc/{bitcoin_query}"),
+        }
+    }
+}
+
+impl QueryParseErrorKind {
+    /// Stable identifier of the error for API clients.
+    pub fn code(&self) -> &'static str {
+        match self {
+            Self::InvalidSource => "bad_source",
+            Self::Evm(error) => match error {
+                EvmQueryParseError::BadChainID => "bad_chain_id",
+                EvmQueryParseError::BadType => "bad_type",
+                EvmQueryParseError::BadAddress => "bad_address",
+                EvmQueryParseError::BadName => "bad_name",
+                EvmQueryParseError::BadSlot => "bad_slot",
+                EvmQueryParseError::BadWindow => "bad_window",
+            },
+            Self::Bitcoin(error) => match error {
+                BitcoinQueryParseError::BadNetwork => "bad_network",
+                BitcoinQueryParseError::BadType => "bad_type",
+                BitcoinQueryParseError::BadAddress => "bad_address",
+            },
+        }
+    }
+
+    pub fn subject(&self) -> &'static str {
+        match self {
+            Self::InvalidSource => "source",
+            Self::Evm(error) => match error {
+                EvmQueryParseError::BadChainID => "chain ID",
+                EvmQueryParseError::BadType => "query type",
+                EvmQueryParseError::BadAddress => "address",
+                EvmQueryParseError::BadName => "ENS name",
+                EvmQueryParseError::BadSlot => "storage slot",
+                EvmQueryParseError::BadWindow => "window",
+            },
+            Self::Bitcoin(error) => match error {
+                BitcoinQueryParseError::BadNetwork => "network",
+                BitcoinQueryParseError::BadType => "query type",
+                BitcoinQueryParseError::BadAddress => "address",
+            },
+        }
+    }
+}
+
+impl From<EvmQueryParseError> for QueryParseErrorKind {
+    fn from(error: EvmQueryParseError) -> Self {
+        Self::Evm(error)
     }
 }
 
-impl std::error::Error for QueryParseError {
-    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
-        None
+impl From<BitcoinQueryParseError> for QueryParseErrorKind {
+    fn from(error: BitcoinQueryParseError) -> Self {
+        Self::Bitcoin(error)
     }
 }
+
+impl Serialize for QueryParseErrorKind {
+    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: serde::Serializer,
+    {
+        serializer.serialize_str(self.code())
+    }
+}
+
+impl std::fmt::Display for QueryParseError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let subject = self.kind.subject();
+
+        match &self.text {
+            Some(text) => write!(f, "Invalid {subject} \"{text}\"")?,
+            None => write!(f, "Missing {subject}")?,
+        }
+
+        write!(
+            f,
+            " at segment {}, expected {}",
+            self.segment, self.expected
+        )
+    }
+}
+
+impl std::error::Error for QueryParseError {}
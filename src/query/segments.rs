@@ -0,0 +1,45 @@
+use std::str::Split;
+
+use super::{QueryParseError, QueryParseErrorKind};
+
+/// The `/`-separated segments of a query path. Remembers the segment read
+/// last, so that parse errors can point at it.
+pub struct Segments<'a> {
+    split: Split<'a, char>,
+    read: usize,
+    last: Option<&'a str>,
+}
+
+impl<'a> Segments<'a> {
+    pub fn new(path: &'a str) -> Self {
+        Self {
+            split: path.split('/'),
+            read: 0,
+            last: None,
+        }
+    }
+
+    /// Returns an error about the segment read last, or about the missing
+    /// segment if the path ended there or it is empty.
+    pub fn error(&self, kind: impl Into<QueryParseErrorKind>, expected: &str) -> QueryParseError {
+        QueryParseError {
+            kind: kind.into(),
+            segment: self.read.saturating_sub(1),
+            text: self
+                .last
+                .filter(|text| !text.is_empty())
+                .map(str::to_string),
+            expected: expected.to_string(),
+        }
+    }
+}
+
+impl<'a> Iterator for Segments<'a> {
+    type Item = &'a str;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        self.read += 1;
+        self.last = self.split.next();
+        self.last
+    }
+}
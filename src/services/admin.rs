@@ -0,0 +1,27 @@
+use actix_web::{get, http::header::AUTHORIZATION, web, HttpRequest, HttpResponse, Responder};
+
+use crate::Executor;
+
+#[get("/api/admin/rpc_health")]
+pub async fn rpc_health(request: HttpRequest, executor: web::Data<Executor>) -> impl Responder {
+    if !is_authorized(&request, &executor) {
+        return HttpResponse::NotFound().finish();
+    }
+
+    HttpResponse::Ok().json(executor.data_source.rpc_health_report())
+}
+
+/// Admin endpoints only exist when a token is configured, and then require
+/// it as a bearer token.
+fn is_authorized(request: &HttpRequest, executor: &Executor) -> bool {
+    let Some(token) = &executor.admin_token else {
+        return false;
+    };
+
+    request
+        .headers()
+        .get(AUTHORIZATION)
+        .and_then(|value| value.to_str().ok())
+        .and_then(|value| value.strip_prefix("Bearer "))
+        .is_some_and(|value| value == token)
+}
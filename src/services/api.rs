@@ -1,6 +1,29 @@
-use actix_web::{get, web, HttpResponse, Responder};
+use actix_web::{get, web, HttpRequest, HttpResponse, Responder};
+use serde::Serialize;
 
-use crate::Executor;
+use super::http_cache;
+use crate::{
+    error::BadgifyError,
+    query::{Query, QueryParseError},
+    Executor,
+};
+
+#[derive(Serialize)]
+struct ErrorBody<'a> {
+    error: &'static str,
+    message: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    query_error: Option<&'a QueryParseError>,
+}
+
+#[derive(Serialize)]
+struct Validation {
+    valid: bool,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    message: Option<String>,
+    #[serde(flatten, skip_serializing_if = "Option::is_none")]
+    error: Option<QueryParseError>,
+}
 
 #[get("/health")]
 pub async fn health() -> impl Responder {
@@ -8,10 +31,50 @@ pub async fn health() -> impl Responder {
 }
 
 #[get("/api/query/{query:.*}")]
-pub async fn query(query: web::Path<String>, executor: web::Data<Executor>) -> impl Responder {
-    let Ok(result) = executor.query_data(&query.to_string()).await else {
-        return HttpResponse::InternalServerError().body("failed");
+pub async fn query(
+    request: HttpRequest,
+    query: web::Path<String>,
+    executor: web::Data<Executor>,
+) -> impl Responder {
+    match executor.query_data(&query.to_string()).await {
+        Ok(result) => http_cache::json_response(&request, &result, result.max_age),
+        Err(err) => render_error(&err),
+    }
+}
+
+pub fn render_error(err: &BadgifyError) -> HttpResponse {
+    let query_error = match err {
+        BadgifyError::InvalidQuery(query_error) => Some(query_error),
+        _ => None,
+    };
+
+    if err.status_code().is_server_error() {
+        println!("Request failed: {err}");
+    }
+
+    HttpResponse::build(err.status_code()).json(ErrorBody {
+        error: err.code(),
+        message: err.public_message(),
+        query_error,
+    })
+}
+
+/// Checks whether a query is well-formed without executing it, pointing at
+/// the offending path segment otherwise.
+#[get("/api/validate/{query:.*}")]
+pub async fn validate(path: web::Path<String>) -> impl Responder {
+    let validation = match Query::parse_path(&path) {
+        Ok(_) => Validation {
+            valid: true,
+            message: None,
+            error: None,
+        },
+        Err(err) => Validation {
+            valid: false,
+            message: Some(err.to_string()),
+            error: Some(err),
+        },
     };
 
-    HttpResponse::Ok().json(result)
+    HttpResponse::Ok().json(validation)
 }
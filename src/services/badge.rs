@@ -1,19 +1,23 @@
 use std::str::FromStr;
 
-use actix_web::{get, http::header::LOCATION, web, HttpResponse, Responder};
+use actix_web::{get, http::header::LOCATION, web, HttpRequest, HttpResponse, Responder};
 use alloy::primitives::U256;
 use bigdecimal::{BigDecimal, ParseBigDecimalError};
 use num::BigInt;
 use serde::{Deserialize, Serialize};
 
+use super::http_cache;
 use crate::{
     badge::{shields_io_data::ShildsIoBadgeData, Badge, Logo},
-    data_source::{SourceResponse, SourceResponseWithMetadata},
+    data_source::{QueryResult, SourceResponse, SourceResponseWithMetadata},
+    utils::unix_now,
     Executor,
 };
 
 const DEFAULT_BELOW_THRESHOLD_COLOR: &str = "yellow";
 const DEFAULT_ABOVE_THRESHOLD_COLOR: &str = "blue";
+const EXPIRED_COLOR: &str = "red";
+const DEFAULT_EXPIRY_WARNING_DAYS: &str = "30";
 
 #[derive(Serialize, Deserialize)]
 struct BadgeQuery {
@@ -21,64 +25,101 @@ struct BadgeQuery {
     label: Option<String>,
     icon: Option<String>,
     warning_threshold: Option<String>,
+    address_format: Option<String>,
+    testnet_label: Option<String>,
 }
 
 #[get("/badge/{badge_query:.*}")]
 pub async fn badge_image(
     badge_query: web::Path<String>,
+    executor: web::Data<Executor>,
     query: web::Query<BadgeQuery>,
 ) -> impl Responder {
-    let query_string = serde_urlencoded::to_string(&query.into_inner()).unwrap();
+    let query_string = serde_urlencoded::to_string(query.into_inner()).unwrap();
     let badge_data_url = format!(
         "https://badgify.io/badge_data/{}?{}",
         badge_query, query_string
     )
-    .trim_end_matches(|c| c == '?')
+    .trim_end_matches('?')
     .to_string();
 
-    HttpResponse::TemporaryRedirect()
-        .insert_header((
-            LOCATION,
-            format!("https://img.shields.io/endpoint?url={}", badge_data_url),
-        ))
-        .finish()
+    let mut response = HttpResponse::TemporaryRedirect();
+    response.insert_header((
+        LOCATION,
+        format!("https://img.shields.io/endpoint?url={}", badge_data_url),
+    ));
+
+    if let Ok(ttl) = executor.cache_ttl(&badge_query) {
+        response.insert_header(http_cache::cache_control(ttl));
+    }
+
+    response.finish()
 }
 
 #[get("/badge_data/{badge_query:.*}")]
 pub async fn badge_data_api(
+    request: HttpRequest,
     badge_query: web::Path<String>,
     executor: web::Data<Executor>,
     query: web::Query<BadgeQuery>,
 ) -> impl Responder {
-    let Ok(result) = executor.query_data(&badge_query.to_string()).await else {
-        return render_failed_badge();
+    let QueryResult {
+        data: result,
+        stale,
+        max_age,
+        ..
+    } = match executor.query_data(&badge_query.to_string()).await {
+        Ok(result) => result,
+        Err(err) => return render_failed_badge(&err.badge_message()),
     };
 
-    let mut badge: Badge = Badge::from(&result);
+    let mut badge: Badge = Badge::from(&*result);
+
+    if let SourceResponse::Address { value } = &result.result {
+        if query.address_format.as_deref() == Some("checksum") {
+            badge.message = value.to_checksum(None);
+        }
+    }
 
     badge.color = if let Some(color) = &query.color {
         Some(color.to_string())
     } else {
         let Ok(color) = extract_threshold_color(&query, &result) else {
-            return render_failed_badge();
+            return render_failed_badge("bad threshold");
         };
 
         color
     };
 
+    if stale {
+        badge.mark_stale();
+    }
+
     if let Some(label) = &query.label {
         badge.label = Some(label.to_string());
     }
 
+    if query.testnet_label.as_deref() == Some("true") && result.metadata.is_testnet() {
+        badge.label = Some(match badge.label {
+            Some(label) => format!("{label} (testnet)"),
+            None => "testnet".to_string(),
+        });
+    }
+
     if let Some(icon) = &query.icon {
         badge.icon = Some(Logo::Slug(icon.to_string()));
+    } else if let Some(svg) = executor.data_source.logo_svg(&result.metadata).await {
+        badge.icon = Some(Logo::Svg(svg.to_string()));
     }
 
-    render_badge(badge)
+    // Shields.io would otherwise keep the badge for its own default time.
+    badge.cache_seconds = Some(max_age.as_secs());
+
+    http_cache::json_response(&request, &ShildsIoBadgeData::from(badge), max_age)
 }
 
-pub fn render_failed_badge() -> HttpResponse {
-    let mut failure_badge = Badge::new("Failed");
+pub fn render_failed_badge(message: &str) -> HttpResponse {
+    let mut failure_badge = Badge::new(message);
     failure_badge.color = Some("red".to_string());
     failure_badge.label = Some("Badge".to_string());
     failure_badge.is_error = true;
@@ -101,14 +142,51 @@ fn extract_threshold_color(
 ) -> Result<Option<String>, ParseBigDecimalError> {
     match result.result {
         SourceResponse::Decimal { value, decimals } => {
-            let decimal_value = parse_decimal(value, decimals);
-            let warning_threshold =
-                BigDecimal::from_str(query.warning_threshold.as_deref().unwrap_or("0"))?;
-
-            let color = match decimal_value.cmp(&warning_threshold) {
-                std::cmp::Ordering::Less => DEFAULT_BELOW_THRESHOLD_COLOR,
-                std::cmp::Ordering::Equal => DEFAULT_BELOW_THRESHOLD_COLOR,
-                std::cmp::Ordering::Greater => DEFAULT_ABOVE_THRESHOLD_COLOR,
+            decimal_threshold_color(query, parse_decimal(value, decimals))
+        }
+        SourceResponse::SignedDecimal { value, decimals } => {
+            let (sign, abs) = value.into_sign_and_abs();
+            let decimal_value = parse_decimal(abs, decimals);
+            let decimal_value = if sign.is_negative() {
+                -decimal_value
+            } else {
+                decimal_value
+            };
+
+            decimal_threshold_color(query, decimal_value)
+        }
+        SourceResponse::Integer { value } => {
+            decimal_threshold_color(query, parse_decimal(value, 0))
+        }
+        SourceResponse::Timestamp { value } => {
+            let warning_days = BigDecimal::from_str(
+                query
+                    .warning_threshold
+                    .as_deref()
+                    .unwrap_or(DEFAULT_EXPIRY_WARNING_DAYS),
+            )?;
+            let now = unix_now();
+
+            let color = if value <= now {
+                EXPIRED_COLOR
+            } else if BigDecimal::from(value - now) <= warning_days * BigDecimal::from(86400) {
+                DEFAULT_BELOW_THRESHOLD_COLOR
+            } else {
+                DEFAULT_ABOVE_THRESHOLD_COLOR
+            };
+
+            Ok(Some(color.into()))
+        }
+        SourceResponse::Duration { seconds } => {
+            // Durations (e.g. block age) only turn yellow once they exceed an explicit threshold.
+            let Some(warning_threshold) = query.warning_threshold.as_deref() else {
+                return Ok(None);
+            };
+
+            let color = if BigDecimal::from(seconds) > BigDecimal::from_str(warning_threshold)? {
+                DEFAULT_BELOW_THRESHOLD_COLOR
+            } else {
+                DEFAULT_ABOVE_THRESHOLD_COLOR
             };
 
             Ok(Some(color.into()))
@@ -116,3 +194,19 @@ fn extract_threshold_color(
         _ => Ok(None),
     }
 }
+
+fn decimal_threshold_color(
+    query: &web::Query<BadgeQuery>,
+    decimal_value: BigDecimal,
+) -> Result<Option<String>, ParseBigDecimalError> {
+    let warning_threshold =
+        BigDecimal::from_str(query.warning_threshold.as_deref().unwrap_or("0"))?;
+
+    let color = match decimal_value.cmp(&warning_threshold) {
+        std::cmp::Ordering::Less => DEFAULT_BELOW_THRESHOLD_COLOR,
+        std::cmp::Ordering::Equal => DEFAULT_BELOW_THRESHOLD_COLOR,
+        std::cmp::Ordering::Greater => DEFAULT_ABOVE_THRESHOLD_COLOR,
+    };
+
+    Ok(Some(color.into()))
+}
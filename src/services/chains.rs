@@ -0,0 +1,60 @@
+use actix_web::{get, web, HttpResponse, Responder};
+use serde::{Deserialize, Serialize};
+
+use super::api::render_error;
+use crate::{data_source::chains::ChainInfo, error::BadgifyError, types::ChainID, Executor};
+
+const DEFAULT_PAGE_SIZE: usize = 50;
+const MAX_PAGE_SIZE: usize = 500;
+
+#[derive(Deserialize)]
+struct ChainsQuery {
+    q: Option<String>,
+    testnet: Option<bool>,
+    offset: Option<usize>,
+    limit: Option<usize>,
+}
+
+#[derive(Serialize)]
+struct ChainsPage {
+    total: usize,
+    offset: usize,
+    limit: usize,
+    chains: Vec<ChainInfo>,
+}
+
+#[get("/api/chains")]
+pub async fn chains(
+    query: web::Query<ChainsQuery>,
+    executor: web::Data<Executor>,
+) -> impl Responder {
+    let offset = query.offset.unwrap_or(0);
+    let limit = query.limit.unwrap_or(DEFAULT_PAGE_SIZE).min(MAX_PAGE_SIZE);
+
+    let matching: Vec<ChainInfo> = executor
+        .data_source
+        .chains()
+        .into_iter()
+        .filter(|chain| query.q.as_deref().is_none_or(|q| chain.matches(q)))
+        .filter(|chain| query.testnet.is_none_or(|testnet| chain.testnet == testnet))
+        .collect();
+
+    HttpResponse::Ok().json(ChainsPage {
+        total: matching.len(),
+        offset,
+        limit,
+        chains: matching.into_iter().skip(offset).take(limit).collect(),
+    })
+}
+
+#[get("/api/chains/{id}")]
+pub async fn chain_by_id(id: web::Path<String>, executor: web::Data<Executor>) -> impl Responder {
+    match executor.data_source.chain(&id) {
+        Some(chain) => HttpResponse::Ok().json(chain),
+        None => render_error(&match id.parse::<ChainID>() {
+            Ok(chain_id) => BadgifyError::UnknownChain(chain_id),
+            // Short names and Bitcoin networks have no chain ID.
+            Err(_) => BadgifyError::NotFound(format!("Chain {id} not found")),
+        }),
+    }
+}
@@ -0,0 +1,74 @@
+use actix_web::{get, web, HttpResponse, Responder};
+use serde::{Deserialize, Serialize};
+
+use super::api::render_error;
+use crate::{error::BadgifyError, history::HistoryPoint, query::Query, utils::unix_now, Executor};
+
+const DEFAULT_RANGE_SECS: u64 = 30 * 24 * 60 * 60;
+const MAX_POINTS: u64 = 1_000;
+
+#[derive(Deserialize)]
+struct HistoryQuery {
+    from: Option<u64>,
+    to: Option<u64>,
+    interval: Option<u64>,
+}
+
+#[derive(Serialize)]
+struct HistorySeries {
+    from: u64,
+    to: u64,
+    interval: u64,
+    points: Vec<HistoryPoint>,
+}
+
+/// Returns the recorded results of a tracked query between the unix times
+/// `from` and `to`, one per `interval` seconds. Defaults to the last 30 days.
+#[get("/api/history/{query:.*}")]
+pub async fn history(
+    path: web::Path<String>,
+    params: web::Query<HistoryQuery>,
+    executor: web::Data<Executor>,
+) -> impl Responder {
+    let Some(history) = &executor.history else {
+        return render_error(&BadgifyError::Unavailable(
+            "History is not enabled".to_string(),
+        ));
+    };
+
+    let query = match Query::parse_path(&path) {
+        Ok(query) => query,
+        Err(err) => return render_error(&err.into()),
+    };
+
+    if !history.is_tracked(&query) {
+        return render_error(&BadgifyError::NotFound(format!(
+            "History of {path} is not recorded"
+        )));
+    }
+
+    let to = params.to.unwrap_or_else(unix_now);
+    let from = params
+        .from
+        .unwrap_or_else(|| to.saturating_sub(DEFAULT_RANGE_SECS));
+    // Long ranges are downsampled so that responses stay small.
+    let interval = params
+        .interval
+        .unwrap_or(1)
+        .max(to.saturating_sub(from).div_ceil(MAX_POINTS))
+        .max(1);
+
+    let series = history
+        .blocking(move |history| history.series(&query, from, to, interval))
+        .await;
+
+    match series {
+        Ok(points) => HttpResponse::Ok().json(HistorySeries {
+            from,
+            to,
+            interval,
+            points,
+        }),
+        Err(err) => render_error(&err),
+    }
+}
@@ -0,0 +1,61 @@
+use std::time::Duration;
+
+use actix_web::{
+    http::header::{CacheControl, CacheDirective, ContentType, ETag, EntityTag, IfNoneMatch},
+    HttpMessage, HttpRequest, HttpResponse,
+};
+use alloy::{hex, primitives::keccak256};
+use serde::Serialize;
+
+use crate::error::BadgifyError;
+
+use super::api::render_error;
+
+/// Responds with `body` as JSON under a strong ETag of its content, or with
+/// 304 Not Modified if the client's `If-None-Match` already has it.
+pub fn json_response<T: Serialize>(
+    request: &HttpRequest,
+    body: &T,
+    max_age: Duration,
+) -> HttpResponse {
+    let body = match serde_json::to_vec(body) {
+        Ok(body) => body,
+        Err(err) => {
+            return render_error(&BadgifyError::Internal(format!(
+                "Cannot serialize response: {err}"
+            )))
+        }
+    };
+
+    let etag = EntityTag::new_strong(hex::encode(&keccak256(&body)[..16]));
+    let not_modified = match request.get_header::<IfNoneMatch>() {
+        Some(IfNoneMatch::Any) => true,
+        Some(IfNoneMatch::Items(tags)) => tags.iter().any(|tag| tag.weak_eq(&etag)),
+        None => false,
+    };
+
+    if not_modified {
+        return HttpResponse::NotModified()
+            .insert_header(ETag(etag))
+            .insert_header(cache_control(max_age))
+            .finish();
+    }
+
+    HttpResponse::Ok()
+        .insert_header(ETag(etag))
+        .insert_header(cache_control(max_age))
+        .content_type(ContentType::json())
+        .body(body)
+}
+
+/// Lets downstream caches, e.g. GitHub's image proxy, keep a response for as
+/// long as we cache the result behind it.
+pub fn cache_control(max_age: Duration) -> CacheControl {
+    match max_age.as_secs() {
+        0 => CacheControl(vec![CacheDirective::NoCache]),
+        secs => CacheControl(vec![
+            CacheDirective::Public,
+            CacheDirective::MaxAge(secs.try_into().unwrap_or(u32::MAX)),
+        ]),
+    }
+}
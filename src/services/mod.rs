@@ -1,3 +1,7 @@
+pub mod admin;
 pub mod api;
 pub mod badge;
+pub mod chains;
+pub mod history;
+pub mod http_cache;
 pub mod scanner;
@@ -4,10 +4,17 @@ use crate::Executor;
 
 #[get("/scanner/{query:.*}")]
 pub async fn scanner(query: web::Path<String>, executor: web::Data<Executor>) -> impl Responder {
-    let Ok(scanner_link) = executor.get_scanner_link(&query.to_string()).await else {
-        return HttpResponse::InternalServerError()
-            .content_type("text/plain")
-            .body("Redirect Failure");
+    let scanner_link = match executor.get_scanner_link(&query.to_string()).await {
+        Ok(scanner_link) => scanner_link,
+        Err(err) => {
+            if err.status_code().is_server_error() {
+                println!("Scanner link failed: {err}");
+            }
+
+            return HttpResponse::build(err.status_code())
+                .content_type("text/plain")
+                .body(format!("Redirect Failure: {}", err.public_message()));
+        }
     };
 
     HttpResponse::TemporaryRedirect()
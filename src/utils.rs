@@ -2,7 +2,10 @@ use alloy::{
     hex::FromHex,
     primitives::{Address, U256},
 };
-use std::str::FromStr;
+use std::{
+    str::FromStr,
+    time::{SystemTime, UNIX_EPOCH},
+};
 
 #[derive(Debug)]
 pub enum ParseU256Error {
@@ -69,3 +72,95 @@ where
             .map_err(|_| ParseAddressError)
     }
 }
+
+/// Returns the current unix time in seconds.
+pub fn unix_now() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|d| d.as_secs())
+        .unwrap_or_default()
+}
+
+/// Formats `timestamp` relative to `now` (both unix seconds), e.g. "in 42 days"
+/// or "3 hours ago".
+pub fn format_relative_time(timestamp: u64, now: u64) -> String {
+    if timestamp == now {
+        return "now".to_string();
+    }
+
+    if timestamp > now {
+        format!("in {}", format_duration(timestamp - now))
+    } else {
+        format!("{} ago", format_duration(now - timestamp))
+    }
+}
+
+/// Formats a duration in seconds using the largest whole unit that fits,
+/// e.g. "42 days" or "1 minute".
+pub fn format_duration(seconds: u64) -> String {
+    let (amount, unit) = match seconds {
+        d if d < 60 => (d, "second"),
+        d if d < 60 * 60 => (d / 60, "minute"),
+        d if d < 24 * 60 * 60 => (d / (60 * 60), "hour"),
+        d => (d / (24 * 60 * 60), "day"),
+    };
+
+    let plural = if amount == 1 { "" } else { "s" };
+
+    format!("{amount} {unit}{plural}")
+}
+
+/// Shortens `address` to its checksummed first and last four hex digits,
+/// e.g. "0xd8dA…6045".
+pub fn shorten_address(address: &Address) -> String {
+    let checksummed = address.to_checksum(None);
+    format!(
+        "{}…{}",
+        &checksummed[..6],
+        &checksummed[checksummed.len() - 4..]
+    )
+}
+
+/// Inserts a comma between every group of three digits, e.g. "1234567" becomes
+/// "1,234,567".
+pub fn group_thousands(digits: &str) -> String {
+    let mut grouped = String::with_capacity(digits.len() + digits.len() / 3);
+
+    for (i, c) in digits.chars().enumerate() {
+        if i > 0 && (digits.len() - i).is_multiple_of(3) {
+            grouped.push(',');
+        }
+        grouped.push(c);
+    }
+
+    grouped
+}
+
+/// Replaces every URL in `text` with `<url>`. RPC URLs may hold API keys in
+/// their path, query or credentials, and error messages of HTTP clients
+/// repeat the URL they failed on.
+pub fn strip_urls(text: &str) -> String {
+    let mut stripped = String::with_capacity(text.len());
+    let mut rest = text;
+
+    while let Some(separator) = rest.find("://") {
+        let scheme_start = rest[..separator]
+            .rfind(|c: char| !(c.is_ascii_alphanumeric() || matches!(c, '+' | '-' | '.')))
+            .map_or(0, |index| index + 1);
+        let url_end = rest[separator..]
+            .find(|c: char| c.is_whitespace() || matches!(c, ')' | '"' | '\'' | ',' | '>'))
+            .map_or(rest.len(), |index| separator + index);
+
+        if scheme_start == separator {
+            stripped.push_str(&rest[..separator + 3]);
+            rest = &rest[separator + 3..];
+        } else {
+            stripped.push_str(&rest[..scheme_start]);
+            stripped.push_str("<url>");
+            rest = &rest[url_end..];
+        }
+    }
+
+    stripped.push_str(rest);
+    stripped
+}
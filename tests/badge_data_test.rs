@@ -0,0 +1,30 @@
+use badgify_rs::badge::{shields_io_data::ShildsIoBadgeData, Badge};
+
+#[test]
+fn test_cache_seconds_in_badge_data() {
+    let mut badge = Badge::new("1");
+    badge.cache_seconds = Some(300);
+
+    let data = serde_json::to_value(ShildsIoBadgeData::from(badge)).unwrap();
+
+    assert_eq!(data["cacheSeconds"], 300);
+}
+
+#[test]
+fn test_cache_seconds_omitted_by_default() {
+    let data = serde_json::to_value(ShildsIoBadgeData::from(Badge::new("1"))).unwrap();
+
+    assert!(data.get("cacheSeconds").is_none());
+}
+
+#[test]
+fn test_stale_marker_follows_suffix() {
+    let mut badge = Badge::new("1.5");
+    badge.suffix = Some("ETH".to_string());
+    badge.mark_stale();
+
+    let data = serde_json::to_value(ShildsIoBadgeData::from(badge)).unwrap();
+
+    assert_eq!(data["message"], "1.5 ETH (stale)");
+    assert_eq!(data["color"], "lightgrey");
+}
@@ -0,0 +1,144 @@
+use std::{collections::HashMap, time::Duration};
+
+use badgify_rs::{
+    badge::{shields_io_data::ShildsIoBadgeData, Badge, Logo},
+    config::ChainListConfig,
+    evm_chain_icons::{is_safe_svg, to_svg, ChainIcons, IconImage},
+};
+use tokio::{
+    io::{AsyncReadExt, AsyncWriteExt},
+    net::TcpListener,
+};
+
+const SVG: &str = r#"<svg xmlns="http://www.w3.org/2000/svg"><circle r="1"/></svg>"#;
+
+fn icon_image(format: &str) -> IconImage {
+    IconImage {
+        url: "ipfs://QmIcon".to_string(),
+        width: Some(64),
+        height: Some(48),
+        format: format.to_string(),
+    }
+}
+
+#[test]
+fn test_svg_icons_are_used_as_is() {
+    let svg = r#"<svg xmlns="http://www.w3.org/2000/svg"><circle r="1"/></svg>"#;
+
+    assert_eq!(to_svg(&icon_image("svg"), svg.as_bytes()).unwrap(), svg);
+    assert!(to_svg(&icon_image("svg"), b"<html></html>").is_err());
+}
+
+#[test]
+fn test_unsafe_svg_icons_are_rejected() {
+    let unsafe_svgs = [
+        r#"<svg><script>alert(1)</script></svg>"#,
+        r#"<svg onload="alert(1)"><circle r="1"/></svg>"#,
+        r#"<svg><a href="javascript:alert(1)"><circle r="1"/></a></svg>"#,
+        r#"<svg><image href="https://tracker.example.com/pixel.png"/></svg>"#,
+        r#"<svg><use xlink:href = 'http://example.com/icons.svg#a'/></svg>"#,
+        r#"<svg><rect fill="url(https://example.com/pattern)"/></svg>"#,
+        r#"<svg><image href="data:image/svg+xml;base64,PHN2Zz4="/></svg>"#,
+        r#"<svg><foreignObject><div/></foreignObject></svg>"#,
+        r#"<!DOCTYPE svg [<!ENTITY x "y">]><svg>&x;</svg>"#,
+    ];
+
+    for svg in unsafe_svgs {
+        assert!(!is_safe_svg(svg), "{svg}");
+        assert!(to_svg(&icon_image("svg"), svg.as_bytes()).is_err());
+    }
+}
+
+#[test]
+fn test_self_contained_svg_icons_are_safe() {
+    let safe_svgs = [
+        SVG,
+        r##"<svg><defs><linearGradient id="g"/></defs><rect fill="url(#g)"/><use href="#g"/></svg>"##,
+        r#"<svg><g fill="none" stroke-linejoin="round"><path d="M0 0"/></g></svg>"#,
+        r#"<svg><image href="data:image/png;base64,iVBORw=="/></svg>"#,
+    ];
+
+    for svg in safe_svgs {
+        assert!(is_safe_svg(svg), "{svg}");
+    }
+}
+
+#[test]
+fn test_raster_icons_are_embedded_in_svg() {
+    let svg = to_svg(&icon_image("png"), &[0x89, b'P', b'N', b'G']).unwrap();
+
+    assert!(svg.starts_with("<svg"));
+    assert!(svg.contains(r#"width="64" height="48""#));
+    assert!(svg.contains("href=\"data:image/png;base64,iVBORw==\""));
+
+    assert!(to_svg(&icon_image("gif"), &[]).is_err());
+}
+
+#[test]
+fn test_svg_logo_in_badge_data() {
+    let mut badge = Badge::new("1");
+    badge.icon = Some(Logo::Svg("<svg/>".to_string()));
+
+    let data = serde_json::to_value(ShildsIoBadgeData::from(badge)).unwrap();
+
+    assert_eq!(data["logoSvg"], "<svg/>");
+    assert!(data.get("namedLogo").is_none());
+}
+
+/// Serves `routes` on `listener`, sending bodies without a length so that
+/// they are read until the connection closes.
+fn serve(listener: TcpListener, routes: HashMap<String, Vec<u8>>) {
+    tokio::spawn(async move {
+        loop {
+            let (mut socket, _) = listener.accept().await.unwrap();
+            let mut request = vec![0; 1024];
+            let read = socket.read(&mut request).await.unwrap();
+            let request = String::from_utf8_lossy(&request[..read]);
+            let path = request.split(' ').nth(1).unwrap_or_default();
+
+            let response = match routes.get(path) {
+                Some(body) => [b"HTTP/1.1 200 OK\r\nConnection: close\r\n\r\n", &body[..]].concat(),
+                None => b"HTTP/1.1 404 Not Found\r\nContent-Length: 0\r\n\r\n".to_vec(),
+            };
+            let _ = socket.write_all(&response).await;
+        }
+    });
+}
+
+async fn load_icon(icons: &ChainIcons, key: &str) -> Option<String> {
+    // Requests made while the icon loads are answered without it.
+    for _ in 0..20 {
+        if let Some(svg) = icons.svg(key).await {
+            return Some(svg.to_string());
+        }
+        tokio::time::sleep(Duration::from_millis(50)).await;
+    }
+
+    None
+}
+
+#[tokio::test]
+async fn test_icons_are_loaded_and_oversized_ones_skipped() {
+    let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+    let url = format!("http://{}", listener.local_addr().unwrap());
+
+    let mut routes = HashMap::new();
+    for (key, svg) in [
+        ("ok", SVG.to_string()),
+        ("huge", format!("<svg>{}</svg>", " ".repeat(100 * 1024))),
+    ] {
+        let definition =
+            serde_json::json!([{ "url": format!("{url}/{key}.svg"), "format": "svg" }]);
+        routes.insert(format!("/{key}.json"), definition.to_string().into_bytes());
+        routes.insert(format!("/{key}.svg"), svg.into_bytes());
+    }
+    serve(listener, routes);
+
+    let icons = ChainIcons::new(&ChainListConfig {
+        icons_source: Some(url),
+        ..ChainListConfig::default()
+    });
+
+    assert_eq!(load_icon(&icons, "ok").await.as_deref(), Some(SVG));
+    assert_eq!(load_icon(&icons, "huge").await, None);
+}
@@ -0,0 +1,71 @@
+use alloy::primitives::U256;
+use badgify_rs::{
+    config::ChainListConfig,
+    evm_chainlist::{bundled_chain_list, EvmChainList},
+};
+
+#[test]
+fn test_bundled_chain_list_parses() {
+    let chains = bundled_chain_list().unwrap();
+
+    let mainnet = chains
+        .iter()
+        .find(|chain| chain.chain_id == U256::from(1))
+        .unwrap();
+
+    assert_eq!(mainnet.name, "Ethereum Mainnet");
+    assert_eq!(mainnet.native_currency.symbol, "ETH");
+    assert!(!mainnet.rpc.is_empty());
+}
+
+#[test]
+fn test_bundled_chain_list_has_unique_chain_ids() {
+    let chains = bundled_chain_list().unwrap();
+
+    let mut chain_ids: Vec<_> = chains.iter().map(|chain| chain.chain_id).collect();
+    chain_ids.sort();
+    chain_ids.dedup();
+
+    assert_eq!(chain_ids.len(), chains.len());
+}
+
+#[test]
+fn test_chain_list_lookups() {
+    let chain_list = EvmChainList::new(&ChainListConfig::default()).unwrap();
+
+    let base = chain_list.get_chain(U256::from(8453)).unwrap();
+    assert_eq!(base.short_name, "base");
+
+    let mainnet = chain_list.get_chain_by_short_name("ETH").unwrap();
+    assert_eq!(mainnet.chain_id, U256::from(1));
+
+    assert!(chain_list.get_chain(U256::from(999_999_999)).is_none());
+    assert!(chain_list.get_chain_by_short_name("unknown").is_none());
+}
+
+#[test]
+fn test_chain_metadata_enrichment() {
+    let chain_list = EvmChainList::new(&ChainListConfig::default()).unwrap();
+
+    let mainnet = chain_list.get_chain(U256::from(1)).unwrap();
+    assert!(!mainnet.is_testnet());
+    assert!(!mainnet.is_deprecated());
+    assert!(mainnet.supports_eip1559());
+    assert!(mainnet.ens.is_some());
+    assert_eq!(mainnet.parent_chain_id(), None);
+
+    let sepolia = chain_list.get_chain(U256::from(11155111)).unwrap();
+    assert!(sepolia.is_testnet());
+
+    let goerli = chain_list.get_chain(U256::from(5)).unwrap();
+    assert!(goerli.is_deprecated());
+
+    let base_sepolia = chain_list.get_chain(U256::from(84532)).unwrap();
+    assert!(base_sepolia.is_testnet());
+    assert_eq!(base_sepolia.parent_chain_id(), Some(U256::from(11155111)));
+
+    let arbitrum = chain_list.get_chain(U256::from(42161)).unwrap();
+    assert!(!arbitrum.is_testnet());
+    assert_eq!(arbitrum.parent_chain_id(), Some(U256::from(1)));
+    assert!(arbitrum.ens.is_none());
+}
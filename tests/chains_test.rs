@@ -0,0 +1,45 @@
+use alloy::primitives::U256;
+use badgify_rs::{
+    config::ChainListConfig, data_source::chains::ChainInfo, evm_chainlist::EvmChainList,
+    query::bitcoin::BitcoinNetwork,
+};
+
+#[test]
+fn test_evm_chain_info() {
+    let chain_list = EvmChainList::new(&ChainListConfig::default()).unwrap();
+
+    let mainnet = ChainInfo::evm(chain_list.get_chain(U256::from(1)).unwrap(), true);
+    assert_eq!(mainnet.id, "1");
+    assert_eq!(mainnet.query_prefix, "evm/1");
+    assert!(!mainnet.testnet);
+    assert!(mainnet.explorer_available);
+
+    let goerli = ChainInfo::evm(chain_list.get_chain(U256::from(5)).unwrap(), false);
+    assert!(goerli.testnet);
+    assert!(goerli.deprecated);
+    assert!(!goerli.rpc_available);
+}
+
+#[test]
+fn test_bitcoin_chain_info() {
+    let mainnet = ChainInfo::bitcoin(BitcoinNetwork::Mainnet);
+    assert_eq!(mainnet.id, "btc-mainnet");
+    assert_eq!(mainnet.query_prefix, "btc/mainnet");
+    assert!(!mainnet.testnet);
+    assert!(mainnet.chain.is_none());
+
+    assert!(ChainInfo::bitcoin(BitcoinNetwork::Signet).testnet);
+}
+
+#[test]
+fn test_chain_info_search() {
+    let chain_list = EvmChainList::new(&ChainListConfig::default()).unwrap();
+    let arbitrum = ChainInfo::evm(chain_list.get_chain(U256::from(42161)).unwrap(), true);
+
+    assert!(arbitrum.matches("arbitrum"));
+    assert!(arbitrum.matches("ARB1"));
+    assert!(arbitrum.matches("4216"));
+    assert!(!arbitrum.matches("optimism"));
+
+    assert!(ChainInfo::bitcoin(BitcoinNetwork::Testnet).matches("bitcoin"));
+}
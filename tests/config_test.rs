@@ -0,0 +1,117 @@
+use alloy::primitives::address;
+use badgify_rs::config::Config;
+
+#[test]
+fn test_parse_empty_config_uses_defaults() {
+    let config: Config = "".parse().unwrap();
+
+    assert!(!config.indexer.enabled);
+    assert_eq!(config.indexer.reorg_depth, 12);
+    assert!(config.indexer.tokens.is_empty());
+}
+
+#[test]
+fn test_parse_indexer_config() {
+    let config: Config = r#"
+        [indexer]
+        enabled = true
+        database_path = "/data/index.sqlite"
+
+        [[indexer.tokens]]
+        chain_id = 1
+        address = "0xdac17f958d2ee523a2206206994597c13d831ec7"
+        start_block = 4634748
+    "#
+    .parse()
+    .unwrap();
+
+    assert!(config.indexer.enabled);
+    assert_eq!(config.indexer.tokens.len(), 1);
+    assert_eq!(config.indexer.tokens[0].chain_id, 1);
+    assert_eq!(
+        config.indexer.tokens[0].address,
+        address!("dac17f958d2ee523a2206206994597c13d831ec7")
+    );
+    assert_eq!(config.indexer.tokens[0].start_block, 4634748);
+}
+
+#[test]
+fn test_parse_config_with_unknown_type_fails() {
+    let result: Result<Config, _> = "[indexer]\nenabled = \"yes\"".parse();
+
+    assert!(result.is_err());
+}
+
+#[test]
+fn test_parse_rpc_and_admin_config() {
+    let config: Config = r#"
+        [rpc]
+        failure_threshold = 5
+        max_hedges = 2
+
+        [admin]
+        token = "secret"
+    "#
+    .parse()
+    .unwrap();
+
+    assert_eq!(config.rpc.failure_threshold, 5);
+    assert_eq!(config.rpc.cooldown_secs, 60);
+    assert_eq!(config.rpc.max_hedges, 2);
+    assert_eq!(config.rpc.request_timeout_secs, None);
+    assert_eq!(config.admin.token.as_deref(), Some("secret"));
+}
+
+#[test]
+fn test_parse_quorum_config() {
+    let config: Config = r#"
+        [rpc.quorum]
+        endpoints = 3
+        required = 2
+    "#
+    .parse()
+    .unwrap();
+
+    let quorum = config.rpc.quorum.unwrap();
+    assert_eq!(quorum.endpoints, 3);
+    assert_eq!(quorum.required, 2);
+    assert_eq!(quorum.confirmations, 2);
+}
+
+#[test]
+fn test_parse_chain_list_config() {
+    let config: Config = "[chain_list]\nsource = \"/etc/badgify/chains.json\""
+        .parse()
+        .unwrap();
+
+    assert_eq!(
+        config.chain_list.source.as_deref(),
+        Some("/etc/badgify/chains.json")
+    );
+    assert_eq!(config.chain_list.refresh_interval_secs, 24 * 60 * 60);
+}
+
+#[test]
+fn test_parse_cache_config() {
+    let config: Config =
+        "[cache]\nmax_entries = 500\n[cache.ttl_secs]\nbalance = 30\nerc20_flow = 0"
+            .parse()
+            .unwrap();
+
+    assert_eq!(config.cache.max_entries, 500);
+    assert_eq!(config.cache.ttl_secs.get("balance"), Some(&30));
+    assert_eq!(config.cache.ttl_secs.get("erc20_flow"), Some(&0));
+    assert_eq!(config.cache.stale_max_age_secs, 24 * 60 * 60);
+    assert!(config.cache.stale_database_path.is_none());
+}
+
+#[test]
+fn test_parse_history_config() {
+    let config: Config = "[history]\nenabled = true\nqueries = [\"btc/mainnet/balance/bc1q\"]"
+        .parse()
+        .unwrap();
+
+    assert!(config.history.enabled);
+    assert_eq!(config.history.interval_secs, 60 * 60);
+    assert_eq!(config.history.queries, vec!["btc/mainnet/balance/bc1q"]);
+}
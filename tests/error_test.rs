@@ -0,0 +1,101 @@
+use actix_web::http::StatusCode;
+use alloy::{
+    primitives::{Address, U256},
+    transports::{HttpError, RpcError, TransportError, TransportErrorKind},
+};
+use badgify_rs::{data_source::evm_quorum::tally, error::BadgifyError, query::Query};
+
+#[test]
+fn test_invalid_query_is_a_bad_request() {
+    let err: BadgifyError = Query::parse_path("evm/1/balance/0xnope")
+        .unwrap_err()
+        .into();
+
+    assert_eq!(err.status_code(), StatusCode::BAD_REQUEST);
+    assert_eq!(err.code(), "invalid_query");
+    assert_eq!(err.badge_message(), "bad address");
+    assert!(!err.is_endpoint_failure());
+}
+
+#[test]
+fn test_error_statuses_and_badge_messages() {
+    let unknown_chain = BadgifyError::UnknownChain(U256::from(999));
+    assert_eq!(unknown_chain.status_code(), StatusCode::NOT_FOUND);
+    assert_eq!(unknown_chain.badge_message(), "chain unknown");
+
+    let no_rpc = BadgifyError::NoRpc("No active RPC URLs for chain 1".to_string());
+    assert_eq!(no_rpc.status_code(), StatusCode::BAD_GATEWAY);
+    assert_eq!(no_rpc.badge_message(), "RPC down");
+
+    assert_eq!(
+        BadgifyError::UpstreamTimeout.status_code(),
+        StatusCode::GATEWAY_TIMEOUT
+    );
+    assert_eq!(
+        BadgifyError::RateLimited.status_code(),
+        StatusCode::TOO_MANY_REQUESTS
+    );
+}
+
+#[test]
+fn test_rate_limited_rpc_response() {
+    let err: TransportError = RpcError::Transport(TransportErrorKind::HttpError(HttpError {
+        status: 429,
+        body: String::new(),
+    }));
+
+    let err = BadgifyError::from(err);
+
+    assert!(matches!(err, BadgifyError::RateLimited));
+    assert!(err.is_endpoint_failure());
+}
+
+#[test]
+fn test_quorum_failure_is_a_bad_gateway() {
+    let responses = vec![
+        ("a".to_string(), Address::ZERO),
+        ("b".to_string(), Address::repeat_byte(1)),
+    ];
+    let err = tally(responses, 2, 10, None).unwrap_err();
+
+    assert_eq!(err.status_code(), StatusCode::BAD_GATEWAY);
+    assert_eq!(err.badge_message(), "RPC mismatch");
+    // Disagreeing endpoints are not hidden behind an earlier result.
+    assert!(!err.is_endpoint_failure());
+}
+
+#[test]
+fn test_upstream_errors_hide_urls() {
+    let err: TransportError = TransportErrorKind::custom_str(
+        "error sending request for url (https://eth.example.com/v3/SECRET)",
+    );
+
+    let err = BadgifyError::from(err);
+
+    assert!(matches!(err, BadgifyError::Upstream(_)));
+    assert!(!err.to_string().contains("SECRET"));
+    assert_eq!(err.public_message(), "Upstream request failed");
+}
+
+#[test]
+fn test_reverted_call_is_not_an_endpoint_failure() {
+    let err: TransportError = RpcError::ErrorResp(
+        serde_json::from_str(r#"{"code": 3, "message": "execution reverted", "data": "0x"}"#)
+            .unwrap(),
+    );
+
+    let err = BadgifyError::from(err);
+
+    assert!(matches!(err, BadgifyError::CallFailed(_)));
+    assert!(!err.is_endpoint_failure());
+    assert_eq!(err.badge_message(), "call failed");
+}
+
+#[test]
+fn test_node_errors_are_endpoint_failures() {
+    let err: TransportError = RpcError::ErrorResp(
+        serde_json::from_str(r#"{"code": -32000, "message": "header not found"}"#).unwrap(),
+    );
+
+    assert!(BadgifyError::from(err).is_endpoint_failure());
+}
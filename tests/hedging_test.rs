@@ -0,0 +1,201 @@
+use std::{
+    sync::{Arc, Mutex},
+    time::Duration,
+};
+
+use badgify_rs::{
+    config::RpcConfig,
+    data_source::{evm_hedging::Hedging, rpc_health::RpcHealth},
+    error::BadgifyError,
+    types::ChainID,
+};
+use tokio::time::Instant;
+
+const CHAIN_ID: ChainID = ChainID::from_limbs([1, 0, 0, 0]);
+
+fn rpc_health() -> RpcHealth {
+    RpcHealth::new(&RpcConfig {
+        failure_threshold: 1,
+        ..RpcConfig::default()
+    })
+}
+
+fn hedging(rpc_health: &RpcHealth, max_hedges: usize, deadline: Option<Duration>) -> Hedging<'_> {
+    Hedging {
+        chain_id: CHAIN_ID,
+        rpc_health,
+        redact: &|text| text.to_string(),
+        max_hedges,
+        hedge_delay: Duration::from_millis(20),
+        deadline: deadline.map(|deadline| Instant::now() + deadline),
+    }
+}
+
+fn urls() -> Vec<String> {
+    vec!["a".to_string(), "b".to_string()]
+}
+
+/// An endpoint that answers with its URL after `delay`, unless it is told to
+/// fail, recording every request it finishes.
+#[derive(Clone, Default)]
+struct MockEndpoints {
+    finished: Arc<Mutex<Vec<String>>>,
+}
+
+impl MockEndpoints {
+    fn respond(
+        &self,
+        rpc_url: String,
+        delay: Duration,
+        error: Option<BadgifyError>,
+    ) -> impl std::future::Future<Output = Result<String, BadgifyError>> + Send + 'static {
+        let finished = self.finished.clone();
+
+        async move {
+            tokio::time::sleep(delay).await;
+            finished.lock().unwrap().push(rpc_url.clone());
+
+            match error {
+                Some(err) => Err(err),
+                None => Ok(rpc_url),
+            }
+        }
+    }
+
+    fn finished(&self) -> Vec<String> {
+        self.finished.lock().unwrap().clone()
+    }
+}
+
+#[tokio::test]
+async fn test_slow_endpoint_is_hedged_and_cancelled() {
+    let rpc_health = rpc_health();
+    let endpoints = MockEndpoints::default();
+
+    let result = hedging(&rpc_health, 1, None)
+        .run(urls(), |rpc_url| {
+            let delay = match rpc_url.as_str() {
+                "a" => Duration::from_millis(300),
+                _ => Duration::from_millis(10),
+            };
+            endpoints.respond(rpc_url, delay, None)
+        })
+        .await;
+
+    assert_eq!(result.unwrap(), "b");
+
+    // The losing request is cancelled rather than left running.
+    tokio::time::sleep(Duration::from_millis(400)).await;
+    assert_eq!(endpoints.finished(), ["b"]);
+}
+
+#[tokio::test]
+async fn test_request_latency_is_recorded() {
+    let rpc_health = rpc_health();
+    let endpoints = MockEndpoints::default();
+
+    hedging(&rpc_health, 0, None)
+        .run(urls(), |rpc_url| {
+            endpoints.respond(rpc_url, Duration::from_millis(50), None)
+        })
+        .await
+        .unwrap();
+
+    let report = &rpc_health.report()[&CHAIN_ID.to_string()];
+    let latency_ms = report[0].latency_ms.unwrap();
+    assert_eq!(report[0].url, "a");
+    assert!((50.0..1_000.0).contains(&latency_ms), "{latency_ms}");
+}
+
+#[tokio::test]
+async fn test_without_hedging_slow_endpoint_is_awaited() {
+    let rpc_health = rpc_health();
+    let endpoints = MockEndpoints::default();
+
+    let result = hedging(&rpc_health, 0, None)
+        .run(urls(), |rpc_url| {
+            endpoints.respond(rpc_url, Duration::from_millis(100), None)
+        })
+        .await;
+
+    assert_eq!(result.unwrap(), "a");
+    assert_eq!(endpoints.finished(), ["a"]);
+}
+
+#[tokio::test]
+async fn test_failing_endpoint_falls_over_to_next() {
+    let rpc_health = rpc_health();
+    let endpoints = MockEndpoints::default();
+
+    let result = hedging(&rpc_health, 0, None)
+        .run(urls(), |rpc_url| {
+            let error = (rpc_url == "a").then(|| BadgifyError::Upstream("down".to_string()));
+            endpoints.respond(rpc_url, Duration::ZERO, error)
+        })
+        .await;
+
+    assert_eq!(result.unwrap(), "b");
+    assert!(!rpc_health.is_available(CHAIN_ID, "a"));
+    assert!(rpc_health.is_available(CHAIN_ID, "b"));
+}
+
+#[tokio::test]
+async fn test_call_failures_are_not_retried() {
+    let rpc_health = rpc_health();
+    let endpoints = MockEndpoints::default();
+
+    let result = hedging(&rpc_health, 0, None)
+        .run(urls(), |rpc_url| {
+            let error = Some(BadgifyError::CallFailed("execution reverted".to_string()));
+            endpoints.respond(rpc_url, Duration::ZERO, error)
+        })
+        .await;
+
+    assert!(matches!(result, Err(BadgifyError::CallFailed(_))));
+    assert_eq!(endpoints.finished(), ["a"]);
+    assert!(rpc_health.is_available(CHAIN_ID, "a"));
+}
+
+#[tokio::test]
+async fn test_deadline_fails_endpoints_in_flight() {
+    let rpc_health = rpc_health();
+    let endpoints = MockEndpoints::default();
+
+    let started = Instant::now();
+    let result = hedging(&rpc_health, 1, Some(Duration::from_millis(100)))
+        .run(urls(), |rpc_url| {
+            endpoints.respond(rpc_url, Duration::from_secs(10), None)
+        })
+        .await;
+
+    assert!(matches!(result, Err(BadgifyError::UpstreamTimeout)));
+    assert!(started.elapsed() < Duration::from_secs(1));
+    assert!(!rpc_health.is_available(CHAIN_ID, "a"));
+    assert!(!rpc_health.is_available(CHAIN_ID, "b"));
+}
+
+#[tokio::test]
+async fn test_panicking_request_is_not_blamed_on_endpoint() {
+    let rpc_health = rpc_health();
+    let endpoints = MockEndpoints::default();
+
+    // Printing the panic can take a while when backtraces are enabled.
+    let result = hedging(&rpc_health, 0, Some(Duration::from_secs(3)))
+        .run(urls(), |rpc_url| {
+            let endpoints = endpoints.clone();
+            async move {
+                if rpc_url == "a" {
+                    panic!("predicate bug");
+                }
+                endpoints
+                    .respond(rpc_url, Duration::from_secs(10), None)
+                    .await
+            }
+        })
+        .await;
+
+    // Only the endpoint still in flight at the deadline counts as failed.
+    assert!(matches!(result, Err(BadgifyError::UpstreamTimeout)));
+    assert!(rpc_health.is_available(CHAIN_ID, "a"));
+    assert!(!rpc_health.is_available(CHAIN_ID, "b"));
+}
@@ -0,0 +1,78 @@
+use alloy::primitives::U256;
+use badgify_rs::{
+    config::HistoryConfig, data_source::SourceResponse, history::HistoryStore, query::Query,
+};
+
+const TRACKED: &str = "evm/1/balance/0xd8dA6BF26964aF9D7eEd9e03E53415D37aA96045";
+
+fn open(name: &str) -> (HistoryStore, std::path::PathBuf) {
+    let path = std::env::temp_dir().join(format!(
+        "badgify_history_{name}_{}.sqlite",
+        std::process::id()
+    ));
+    let _ = std::fs::remove_file(&path);
+
+    let store = HistoryStore::open(HistoryConfig {
+        database_path: path.clone(),
+        queries: vec![TRACKED.to_string()],
+        ..HistoryConfig::default()
+    })
+    .unwrap();
+
+    (store, path)
+}
+
+fn integer(value: u64) -> SourceResponse {
+    SourceResponse::Integer {
+        value: U256::from(value),
+    }
+}
+
+#[test]
+fn test_tracked_queries_ignore_address_case() {
+    let (store, path) = open("tracked");
+
+    assert!(store.is_tracked(&Query::parse_path(&TRACKED.to_lowercase()).unwrap()));
+    assert!(!store.is_tracked(&Query::parse_path("evm/10/block_number").unwrap()));
+
+    std::fs::remove_file(path).unwrap();
+}
+
+#[test]
+fn test_series_keeps_last_result_per_interval() {
+    let (store, path) = open("series");
+    let query = Query::parse_path(TRACKED).unwrap();
+
+    for (timestamp, value) in [(100, 1), (150, 2), (210, 3), (290, 4), (310, 5)] {
+        store.record(&query, timestamp, &integer(value)).unwrap();
+    }
+
+    let points = store.series(&query, 100, 300, 100).unwrap();
+    std::fs::remove_file(path).unwrap();
+
+    let points: Vec<_> = points
+        .into_iter()
+        .map(|point| (point.timestamp, point.value))
+        .collect();
+    assert_eq!(points, vec![(150, integer(2)), (290, integer(4))]);
+}
+
+#[test]
+fn test_history_survives_reopening() {
+    let (store, path) = open("reopen");
+    let query = Query::parse_path(TRACKED).unwrap();
+
+    store.record(&query, 100, &integer(7)).unwrap();
+    drop(store);
+
+    let store = HistoryStore::open(HistoryConfig {
+        database_path: path.clone(),
+        ..HistoryConfig::default()
+    })
+    .unwrap();
+    let points = store.series(&query, 0, 1_000, 1).unwrap();
+    std::fs::remove_file(path).unwrap();
+
+    assert_eq!(points.len(), 1);
+    assert_eq!(points[0].value, integer(7));
+}
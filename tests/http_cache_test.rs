@@ -0,0 +1,75 @@
+use std::time::Duration;
+
+use actix_web::{
+    body::MessageBody,
+    http::{
+        header::{CACHE_CONTROL, ETAG, IF_NONE_MATCH},
+        StatusCode,
+    },
+    test::TestRequest,
+    HttpResponse,
+};
+use badgify_rs::services::http_cache::json_response;
+use serde_json::json;
+
+fn header(response: &HttpResponse, name: actix_web::http::header::HeaderName) -> String {
+    response
+        .headers()
+        .get(name)
+        .unwrap()
+        .to_str()
+        .unwrap()
+        .to_string()
+}
+
+#[test]
+fn test_etag_is_stable_for_the_same_body() {
+    let request = TestRequest::default().to_http_request();
+
+    let first = json_response(&request, &json!({ "value": 1 }), Duration::from_secs(30));
+    let second = json_response(&request, &json!({ "value": 1 }), Duration::from_secs(5));
+    let other = json_response(&request, &json!({ "value": 2 }), Duration::from_secs(30));
+
+    assert_eq!(first.status(), StatusCode::OK);
+    assert_eq!(header(&first, ETAG), header(&second, ETAG));
+    assert_ne!(header(&first, ETAG), header(&other, ETAG));
+    assert_eq!(header(&first, CACHE_CONTROL), "public, max-age=30");
+}
+
+#[test]
+fn test_matching_if_none_match_is_not_modified() {
+    let body = json!({ "value": 1 });
+    let etag = header(
+        &json_response(
+            &TestRequest::default().to_http_request(),
+            &body,
+            Duration::from_secs(30),
+        ),
+        ETAG,
+    );
+
+    let request = TestRequest::default()
+        .insert_header((IF_NONE_MATCH, etag.clone()))
+        .to_http_request();
+    let response = json_response(&request, &body, Duration::from_secs(30));
+
+    assert_eq!(response.status(), StatusCode::NOT_MODIFIED);
+    assert_eq!(header(&response, ETAG), etag);
+    assert!(response.into_body().try_into_bytes().unwrap().is_empty());
+
+    let request = TestRequest::default()
+        .insert_header((IF_NONE_MATCH, "\"stale\""))
+        .to_http_request();
+    let response = json_response(&request, &body, Duration::from_secs(30));
+
+    assert_eq!(response.status(), StatusCode::OK);
+}
+
+#[test]
+fn test_zero_max_age_is_not_cached() {
+    let request = TestRequest::default().to_http_request();
+
+    let response = json_response(&request, &json!({ "value": 1 }), Duration::ZERO);
+
+    assert_eq!(header(&response, CACHE_CONTROL), "no-cache");
+}
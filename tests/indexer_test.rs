@@ -0,0 +1,145 @@
+use alloy::primitives::{address, Address, U256};
+use badgify_rs::{
+    config::{IndexedToken, IndexerConfig},
+    data_source::evm_indexer::{HolderIndex, IndexedTransfer},
+    types::ChainID,
+};
+
+const TOKEN: Address = address!("dac17f958d2ee523a2206206994597c13d831ec7");
+const ALICE: Address = address!("0000000000000000000000000000000000000a11");
+const BOB: Address = address!("0000000000000000000000000000000000000b0b");
+
+fn token() -> IndexedToken {
+    IndexedToken {
+        chain_id: 1,
+        address: TOKEN,
+        start_block: 100,
+    }
+}
+
+fn index() -> HolderIndex {
+    HolderIndex::open(IndexerConfig {
+        enabled: true,
+        database_path: ":memory:".into(),
+        reorg_depth: 10,
+        tokens: vec![token()],
+        ..IndexerConfig::default()
+    })
+    .unwrap()
+}
+
+fn transfer(block_number: u64, from: Address, to: Address, value: u64) -> IndexedTransfer {
+    IndexedTransfer {
+        block_number,
+        from,
+        to,
+        value: U256::from(value),
+    }
+}
+
+fn holders(index: &HolderIndex) -> Option<(u64, u64)> {
+    index
+        .holder_count(ChainID::from(1), TOKEN)
+        .unwrap()
+        .map(|count| (count.holders, count.indexed_block))
+}
+
+#[test]
+fn test_counts_holders_across_zero_crossings() {
+    let index = index();
+    assert_eq!(index.resume(&token()).unwrap(), 100);
+    assert_eq!(holders(&index), None);
+
+    // Mints credit only the recipient.
+    index
+        .commit(&token(), &[transfer(110, Address::ZERO, ALICE, 50)], 150)
+        .unwrap();
+    assert_eq!(holders(&index), Some((1, 150)));
+
+    index
+        .commit(&token(), &[transfer(160, ALICE, BOB, 20)], 200)
+        .unwrap();
+    assert_eq!(holders(&index), Some((2, 200)));
+
+    // Emptying a balance drops the holder, burns debit only the sender.
+    index
+        .commit(
+            &token(),
+            &[
+                transfer(210, ALICE, BOB, 30),
+                transfer(220, BOB, Address::ZERO, 10),
+            ],
+            250,
+        )
+        .unwrap();
+    assert_eq!(holders(&index), Some((1, 250)));
+
+    index
+        .commit(&token(), &[transfer(260, BOB, Address::ZERO, 40)], 300)
+        .unwrap();
+    assert_eq!(holders(&index), Some((0, 300)));
+}
+
+#[test]
+fn test_resume_rolls_back_reorgable_blocks() {
+    let index = index();
+    index
+        .commit(&token(), &[transfer(110, Address::ZERO, ALICE, 50)], 150)
+        .unwrap();
+    index
+        .commit(
+            &token(),
+            &[
+                transfer(185, ALICE, BOB, 50),
+                transfer(195, Address::ZERO, ALICE, 5),
+            ],
+            200,
+        )
+        .unwrap();
+    assert_eq!(holders(&index), Some((2, 200)));
+
+    // The last 10 blocks are undone: Bob's transfer stays, the mint goes.
+    assert_eq!(index.resume(&token()).unwrap(), 191);
+    assert_eq!(holders(&index), Some((1, 190)));
+
+    index
+        .commit(&token(), &[transfer(192, BOB, ALICE, 10)], 200)
+        .unwrap();
+    assert_eq!(holders(&index), Some((2, 200)));
+}
+
+#[test]
+fn test_resume_continues_from_cursor() {
+    let index = index();
+    index.commit(&token(), &[], 5_000).unwrap();
+    assert_eq!(index.resume(&token()).unwrap(), 4_991);
+
+    // Rolling back to the start block forgets the cursor altogether.
+    let index = self::index();
+    index
+        .commit(&token(), &[transfer(101, Address::ZERO, ALICE, 1)], 105)
+        .unwrap();
+    assert_eq!(index.resume(&token()).unwrap(), 100);
+    assert_eq!(holders(&index), None);
+}
+
+#[test]
+fn test_counts_are_partial_until_synced() {
+    let index = index();
+    index
+        .commit(&token(), &[transfer(110, Address::ZERO, ALICE, 50)], 150)
+        .unwrap();
+
+    let count = index
+        .holder_count(ChainID::from(1), TOKEN)
+        .unwrap()
+        .unwrap();
+    assert!(count.backfilling);
+
+    index.mark_synced(&token());
+    let count = index
+        .holder_count(ChainID::from(1), TOKEN)
+        .unwrap()
+        .unwrap();
+    assert!(!count.backfilling);
+}
@@ -0,0 +1,27 @@
+use alloy::transports::{RpcError, TransportError, TransportErrorKind};
+use badgify_rs::data_source::evm_logs::is_range_error;
+
+fn error_response(message: &str) -> TransportError {
+    RpcError::ErrorResp(
+        serde_json::from_value(serde_json::json!({ "code": -32005, "message": message })).unwrap(),
+    )
+}
+
+#[test]
+fn test_range_errors_are_recognised() {
+    assert!(is_range_error(&error_response(
+        "query returned more than 10000 results"
+    )));
+    assert!(is_range_error(&error_response("block range is too wide")));
+    assert!(is_range_error(&error_response(
+        "Log response size exceeded"
+    )));
+}
+
+#[test]
+fn test_other_errors_are_not_range_errors() {
+    assert!(!is_range_error(&error_response("header not found")));
+    assert!(!is_range_error(&TransportErrorKind::custom_str(
+        "connection refused"
+    )));
+}
@@ -0,0 +1,248 @@
+use std::sync::{Arc, Mutex};
+
+use alloy::{
+    primitives::{Bytes, U256},
+    rpc::{
+        client::{ClientBuilder, ReqwestClient},
+        types::BlockId,
+    },
+    sol,
+    sol_types::SolValue,
+};
+use badgify_rs::{
+    data_source::evm_multicall::{self, CallResult, ContractCall, Multicall},
+    error::BadgifyError,
+};
+use serde_json::{json, Value};
+use tokio::{
+    io::{AsyncReadExt, AsyncWriteExt},
+    net::TcpListener,
+};
+
+const MULTICALL3: &str = "0xca11bde05977b3631167028862be2a173976ca11";
+
+sol! {
+    #[derive(Debug)]
+    function answer() external view returns (uint256);
+}
+
+/// A JSON-RPC endpoint answering every request with `handler`, recording the
+/// methods called; `aggregate3` stands for calls to Multicall3.
+struct MockRpc {
+    client: ReqwestClient,
+    methods: Arc<Mutex<Vec<String>>>,
+}
+
+impl MockRpc {
+    async fn start(
+        supports_batches: bool,
+        handler: impl Fn(&str, &Value) -> Result<Value, (i64, &'static str)> + Send + Sync + 'static,
+    ) -> Self {
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let url = format!("http://{}", listener.local_addr().unwrap());
+        let methods = Arc::new(Mutex::new(Vec::new()));
+        let handler = Arc::new(handler);
+
+        let recorded = methods.clone();
+        tokio::spawn(async move {
+            loop {
+                let (mut socket, _) = listener.accept().await.unwrap();
+                let request: Value = serde_json::from_slice(&read_body(&mut socket).await).unwrap();
+
+                let response = match request {
+                    Value::Array(_) if !supports_batches => None,
+                    Value::Array(requests) => Some(Value::Array(
+                        requests
+                            .iter()
+                            .map(|request| respond(request, &*handler, &recorded))
+                            .collect(),
+                    )),
+                    request => Some(respond(&request, &*handler, &recorded)),
+                };
+
+                let response = match response {
+                    Some(body) => {
+                        let body = body.to_string();
+                        format!(
+                            "HTTP/1.1 200 OK\r\nContent-Type: application/json\r\nContent-Length: {}\r\nConnection: close\r\n\r\n{body}",
+                            body.len()
+                        )
+                    }
+                    None => {
+                        "HTTP/1.1 400 Bad Request\r\nContent-Length: 0\r\nConnection: close\r\n\r\n"
+                            .to_string()
+                    }
+                };
+                let _ = socket.write_all(response.as_bytes()).await;
+            }
+        });
+
+        Self {
+            client: ClientBuilder::default().http(url.parse().unwrap()),
+            methods,
+        }
+    }
+
+    fn methods(&self) -> Vec<String> {
+        self.methods.lock().unwrap().clone()
+    }
+}
+
+async fn read_body(socket: &mut tokio::net::TcpStream) -> Vec<u8> {
+    let mut request = Vec::new();
+    let mut buffer = [0; 4096];
+
+    loop {
+        let read = socket.read(&mut buffer).await.unwrap();
+        request.extend_from_slice(&buffer[..read]);
+
+        let text = String::from_utf8_lossy(&request);
+        if let Some(header_end) = text.find("\r\n\r\n") {
+            let content_length = text[..header_end]
+                .lines()
+                .find_map(|line| {
+                    let (name, value) = line.split_once(':')?;
+                    name.eq_ignore_ascii_case("content-length")
+                        .then(|| value.trim().parse::<usize>().unwrap())
+                })
+                .unwrap_or(0);
+
+            if request.len() >= header_end + 4 + content_length {
+                return request[header_end + 4..header_end + 4 + content_length].to_vec();
+            }
+        }
+    }
+}
+
+fn respond(
+    request: &Value,
+    handler: &(impl Fn(&str, &Value) -> Result<Value, (i64, &'static str)> + ?Sized),
+    recorded: &Mutex<Vec<String>>,
+) -> Value {
+    let method = request["method"].as_str().unwrap();
+    let params = &request["params"];
+    let method = match method {
+        "eth_call" if params[0]["to"].as_str().unwrap().to_lowercase() == MULTICALL3 => {
+            "aggregate3"
+        }
+        method => method,
+    };
+    recorded.lock().unwrap().push(method.to_string());
+
+    match handler(method, params) {
+        Ok(result) => json!({ "jsonrpc": "2.0", "id": request["id"], "result": result }),
+        Err((code, message)) => json!({
+            "jsonrpc": "2.0",
+            "id": request["id"],
+            "error": { "code": code, "message": message },
+        }),
+    }
+}
+
+fn calls() -> Vec<ContractCall> {
+    [
+        "0x1111111111111111111111111111111111111111",
+        "0x2222222222222222222222222222222222222222",
+    ]
+    .iter()
+    .map(|target| ContractCall::new(target.parse().unwrap(), &answerCall {}))
+    .collect()
+}
+
+fn word(value: u64) -> Bytes {
+    U256::from(value).abi_encode().into()
+}
+
+async fn call(rpc: &MockRpc, multicall: &Multicall) -> Result<Vec<CallResult>, BadgifyError> {
+    multicall
+        .call(U256::from(1), &rpc.client, &calls(), BlockId::latest())
+        .await
+}
+
+#[tokio::test]
+async fn test_aggregate3_reports_failed_calls_separately() {
+    let rpc = MockRpc::start(true, |method, _| match method {
+        "eth_getCode" => Ok(json!("0x6080")),
+        "aggregate3" => {
+            let results = vec![(true, word(42)), (false, Bytes::from_static(b"nope"))];
+            Ok(json!(Bytes::from((results,).abi_encode_params())))
+        }
+        _ => Err((-32601, "method not found")),
+    })
+    .await;
+    let multicall = Multicall::default();
+
+    let results = call(&rpc, &multicall).await.unwrap();
+
+    assert_eq!(
+        evm_multicall::decode::<answerCall>(&results[0]).unwrap()._0,
+        U256::from(42)
+    );
+    let err = evm_multicall::decode::<answerCall>(&results[1]).unwrap_err();
+    assert!(matches!(err, BadgifyError::CallFailed(_)));
+
+    // The deployment is only looked up once per chain.
+    call(&rpc, &multicall).await.unwrap();
+    assert_eq!(rpc.methods(), ["eth_getCode", "aggregate3", "aggregate3"]);
+}
+
+#[tokio::test]
+async fn test_falls_back_to_batch_without_multicall3() {
+    let rpc = MockRpc::start(true, |method, params| match method {
+        "eth_getCode" => Ok(json!("0x")),
+        "eth_call" if params[0]["to"] == "0x1111111111111111111111111111111111111111" => {
+            Ok(json!(word(7)))
+        }
+        "eth_call" => Err((3, "execution reverted")),
+        _ => Err((-32601, "method not found")),
+    })
+    .await;
+
+    let results = call(&rpc, &Multicall::default()).await.unwrap();
+
+    assert_eq!(results[0].as_ref().unwrap(), &word(7));
+    assert!(matches!(results[1], Err(BadgifyError::CallFailed(_))));
+    assert_eq!(rpc.methods(), ["eth_getCode", "eth_call", "eth_call"]);
+}
+
+#[tokio::test]
+async fn test_falls_back_to_sequential_calls_without_batches() {
+    let rpc = MockRpc::start(false, |method, _| match method {
+        "eth_getCode" => Ok(json!("0x")),
+        "eth_call" => Ok(json!(word(7))),
+        _ => Err((-32601, "method not found")),
+    })
+    .await;
+
+    let results = call(&rpc, &Multicall::default()).await.unwrap();
+
+    assert_eq!(results.len(), 2);
+    assert!(results
+        .iter()
+        .all(|result| result.as_ref().unwrap() == &word(7)));
+    assert_eq!(rpc.methods(), ["eth_getCode", "eth_call", "eth_call"]);
+}
+
+#[tokio::test]
+async fn test_all_calls_failing_at_the_endpoint_is_an_error() {
+    let rpc = MockRpc::start(true, |method, _| match method {
+        "eth_getCode" => Ok(json!("0x")),
+        _ => Err((-32000, "header not found")),
+    })
+    .await;
+
+    let err = call(&rpc, &Multicall::default()).await.unwrap_err();
+    assert!(matches!(err, BadgifyError::Upstream(_)));
+
+    // Calls that every endpoint would revert are answers, not failures.
+    let rpc = MockRpc::start(true, |method, _| match method {
+        "eth_getCode" => Ok(json!("0x")),
+        _ => Err((3, "execution reverted")),
+    })
+    .await;
+
+    let results = call(&rpc, &Multicall::default()).await.unwrap();
+    assert!(results
+        .iter()
+        .all(|result| matches!(result, Err(BadgifyError::CallFailed(_)))));
+}
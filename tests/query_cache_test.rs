@@ -0,0 +1,157 @@
+use std::{
+    sync::{
+        atomic::{AtomicUsize, Ordering},
+        Arc,
+    },
+    time::Duration,
+};
+
+use badgify_rs::{config::CacheConfig, data_source::query_cache::QueryCache, error::BadgifyError};
+
+fn cache(max_entries: usize, ttl_secs: &[(&str, u64)]) -> QueryCache<u64> {
+    QueryCache::new(&CacheConfig {
+        max_entries,
+        ttl_secs: ttl_secs
+            .iter()
+            .map(|(kind, secs)| (kind.to_string(), *secs))
+            .collect(),
+        ..CacheConfig::default()
+    })
+}
+
+async fn fetch(
+    cache: &QueryCache<u64>,
+    calls: &AtomicUsize,
+    key: &str,
+    head: Option<u64>,
+) -> Result<Arc<u64>, BadgifyError> {
+    cache
+        .get_or_fetch(key.to_string(), "balance", head, || async {
+            Ok(Arc::new(calls.fetch_add(1, Ordering::SeqCst) as u64))
+        })
+        .await
+        .map(|(result, _)| result)
+}
+
+#[test]
+fn test_ttl_defaults_and_overrides() {
+    let cache = cache(10, &[("balance", 15)]);
+
+    assert_eq!(cache.ttl("balance"), Duration::from_secs(15));
+    assert_eq!(cache.ttl("block_number"), Duration::from_secs(5));
+    assert_eq!(cache.ttl("ens_expiry"), Duration::from_secs(60 * 60));
+    assert_eq!(cache.ttl("btc_balance"), Duration::from_secs(60));
+}
+
+#[tokio::test]
+async fn test_caches_results_within_ttl() {
+    let cache = cache(10, &[]);
+    let calls = AtomicUsize::new(0);
+
+    assert_eq!(*fetch(&cache, &calls, "a", None).await.unwrap(), 0);
+    assert_eq!(*fetch(&cache, &calls, "a", None).await.unwrap(), 0);
+    assert_eq!(*fetch(&cache, &calls, "b", None).await.unwrap(), 1);
+    assert_eq!(calls.load(Ordering::SeqCst), 2);
+}
+
+#[tokio::test]
+async fn test_zero_ttl_disables_caching() {
+    let cache = cache(10, &[("balance", 0)]);
+    let calls = AtomicUsize::new(0);
+
+    fetch(&cache, &calls, "a", None).await.unwrap();
+    fetch(&cache, &calls, "a", None).await.unwrap();
+
+    assert_eq!(calls.load(Ordering::SeqCst), 2);
+    assert!(cache.is_empty());
+}
+
+#[tokio::test]
+async fn test_head_change_invalidates_result() {
+    let cache = cache(10, &[]);
+    let calls = AtomicUsize::new(0);
+
+    assert_eq!(*fetch(&cache, &calls, "a", Some(100)).await.unwrap(), 0);
+    assert_eq!(*fetch(&cache, &calls, "a", Some(100)).await.unwrap(), 0);
+    assert_eq!(*fetch(&cache, &calls, "a", Some(101)).await.unwrap(), 1);
+    // Without a known head, the TTL applies.
+    assert_eq!(*fetch(&cache, &calls, "a", None).await.unwrap(), 1);
+}
+
+#[tokio::test]
+async fn test_errors_are_not_cached() {
+    let cache = cache(10, &[]);
+    let calls = AtomicUsize::new(0);
+
+    let result = cache
+        .get_or_fetch("a".to_string(), "balance", None, || async {
+            Err(BadgifyError::UpstreamTimeout)
+        })
+        .await;
+
+    assert!(matches!(result, Err(BadgifyError::UpstreamTimeout)));
+    assert_eq!(*fetch(&cache, &calls, "a", None).await.unwrap(), 0);
+}
+
+#[tokio::test]
+async fn test_returns_remaining_ttl() {
+    let cache = cache(10, &[("balance", 2), ("block_number", 0)]);
+    let fetch = || async { Ok(Arc::new(1)) };
+
+    let (_, max_age) = cache
+        .get_or_fetch("a".to_string(), "balance", None, fetch)
+        .await
+        .unwrap();
+    assert!(max_age > Duration::from_millis(1_900));
+
+    tokio::time::sleep(Duration::from_millis(1_100)).await;
+    let (_, max_age) = cache
+        .get_or_fetch("a".to_string(), "balance", None, fetch)
+        .await
+        .unwrap();
+    assert!(max_age < Duration::from_millis(900));
+
+    let (_, max_age) = cache
+        .get_or_fetch("b".to_string(), "block_number", None, fetch)
+        .await
+        .unwrap();
+    assert_eq!(max_age, Duration::ZERO);
+}
+
+#[tokio::test]
+async fn test_concurrent_requests_are_coalesced() {
+    let cache = cache(10, &[]);
+    let calls = AtomicUsize::new(0);
+
+    let slow_fetch = || async {
+        tokio::time::sleep(Duration::from_millis(50)).await;
+        Ok(Arc::new(calls.fetch_add(1, Ordering::SeqCst) as u64))
+    };
+
+    let (first, second, third) = tokio::join!(
+        cache.get_or_fetch("a".to_string(), "balance", None, slow_fetch),
+        cache.get_or_fetch("a".to_string(), "balance", None, slow_fetch),
+        cache.get_or_fetch("a".to_string(), "balance", None, slow_fetch),
+    );
+
+    assert_eq!(calls.load(Ordering::SeqCst), 1);
+    assert_eq!(
+        (*first.unwrap().0, *second.unwrap().0, *third.unwrap().0),
+        (0, 0, 0)
+    );
+}
+
+#[tokio::test]
+async fn test_evicts_least_recently_used() {
+    let cache = cache(2, &[]);
+    let calls = AtomicUsize::new(0);
+
+    fetch(&cache, &calls, "a", None).await.unwrap();
+    fetch(&cache, &calls, "b", None).await.unwrap();
+    fetch(&cache, &calls, "a", None).await.unwrap();
+    fetch(&cache, &calls, "c", None).await.unwrap();
+
+    assert_eq!(cache.len(), 2);
+    assert_eq!(*fetch(&cache, &calls, "a", None).await.unwrap(), 0);
+    assert_eq!(*fetch(&cache, &calls, "b", None).await.unwrap(), 3);
+}
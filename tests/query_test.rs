@@ -1,5 +1,9 @@
 use alloy::primitives::{address, U256};
-use badgify_rs::query::{EvmQuery, Query};
+use badgify_rs::query::{
+    bitcoin::{BitcoinNetwork, BitcoinQuery, BitcoinQueryParseError},
+    BlockWindow, ChainMetric, EvmQuery, EvmQueryParseError, FlowMetric, ProxySlot, Query,
+    QueryParseError, QueryParseErrorKind, SafeField, StorageKind,
+};
 
 #[test]
 fn test_parsing_valid_evm_balance_query() {
@@ -30,3 +34,369 @@ fn test_parsing_valid_evm_erc20_query() {
               address == address!("d8dA6BF26964aF9D7eEd9e03E53415D37aA96045")
     ));
 }
+
+#[test]
+fn test_parsing_valid_evm_safe_query() {
+    let res = Query::parse_path("evm/1/safe/0xd8dA6BF26964aF9D7eEd9e03E53415D37aA96045/threshold");
+
+    assert!(matches!(
+        res,
+        Ok(Query::Evm(EvmQuery::Safe {
+            chain_id,
+            safe_address,
+            field: SafeField::Threshold,
+        })) if chain_id == U256::from(1_u8) &&
+              safe_address == address!("d8dA6BF26964aF9D7eEd9e03E53415D37aA96045")
+    ));
+}
+
+#[test]
+fn test_parsing_evm_safe_query_with_bad_field() {
+    let res = Query::parse_path("evm/1/safe/0xd8dA6BF26964aF9D7eEd9e03E53415D37aA96045/modules");
+
+    assert!(matches!(
+        res,
+        Err(QueryParseError {
+            kind: QueryParseErrorKind::Evm(EvmQueryParseError::BadType),
+            ..
+        })
+    ));
+}
+
+#[test]
+fn test_parsing_valid_evm_ens_expiry_query() {
+    let res = Query::parse_path("evm/1/ens_expiry/Vitalik.eth");
+
+    assert!(matches!(
+        res,
+        Ok(Query::Evm(EvmQuery::EnsExpiry {
+            chain_id,
+            name,
+        })) if chain_id == U256::from(1_u8) && name == "vitalik.eth"
+    ));
+}
+
+#[test]
+fn test_parsing_evm_ens_expiry_query_with_subdomain() {
+    let res = Query::parse_path("evm/1/ens_expiry/pay.vitalik.eth");
+
+    assert!(matches!(
+        res,
+        Err(QueryParseError {
+            kind: QueryParseErrorKind::Evm(EvmQueryParseError::BadName),
+            ..
+        })
+    ));
+}
+
+#[test]
+fn test_parsing_valid_evm_proxy_query() {
+    let res =
+        Query::parse_path("evm/1/proxy/0xA0b86991c6218b36c1d19D4a2e9Eb0cE3606eB48/implementation");
+
+    assert!(matches!(
+        res,
+        Ok(Query::Evm(EvmQuery::Proxy {
+            chain_id,
+            proxy_address,
+            slot: ProxySlot::Implementation,
+        })) if chain_id == U256::from(1_u8) &&
+              proxy_address == address!("A0b86991c6218b36c1d19D4a2e9Eb0cE3606eB48")
+    ));
+}
+
+#[test]
+fn test_parsing_valid_evm_owner_query() {
+    let res = Query::parse_path("evm/10/owner/0xA0b86991c6218b36c1d19D4a2e9Eb0cE3606eB48");
+
+    assert!(matches!(
+        res,
+        Ok(Query::Evm(EvmQuery::Owner {
+            chain_id,
+            contract_address,
+        })) if chain_id == U256::from(10_u8) &&
+              contract_address == address!("A0b86991c6218b36c1d19D4a2e9Eb0cE3606eB48")
+    ));
+}
+
+#[test]
+fn test_parsing_valid_evm_storage_query() {
+    let res =
+        Query::parse_path("evm/1/storage/0xA0b86991c6218b36c1d19D4a2e9Eb0cE3606eB48/0x0a/address");
+
+    assert!(matches!(
+        res,
+        Ok(Query::Evm(EvmQuery::Storage {
+            chain_id,
+            contract_address,
+            slot,
+            kind: StorageKind::Address,
+        })) if chain_id == U256::from(1_u8) &&
+              contract_address == address!("A0b86991c6218b36c1d19D4a2e9Eb0cE3606eB48") &&
+              slot == U256::from(10_u8)
+    ));
+}
+
+#[test]
+fn test_parsing_evm_storage_query_defaults_to_uint() {
+    let res = Query::parse_path("evm/1/storage/0xA0b86991c6218b36c1d19D4a2e9Eb0cE3606eB48/3");
+
+    assert!(matches!(
+        res,
+        Ok(Query::Evm(EvmQuery::Storage {
+            kind: StorageKind::Uint,
+            ..
+        }))
+    ));
+}
+
+#[test]
+fn test_parsing_valid_evm_is_contract_query() {
+    let res = Query::parse_path("evm/1/is_contract/0xd8dA6BF26964aF9D7eEd9e03E53415D37aA96045");
+
+    assert!(matches!(
+        res,
+        Ok(Query::Evm(EvmQuery::IsContract {
+            chain_id,
+            address,
+        })) if chain_id == U256::from(1_u8) &&
+              address == address!("d8dA6BF26964aF9D7eEd9e03E53415D37aA96045")
+    ));
+}
+
+#[test]
+fn test_parsing_valid_evm_chain_status_query() {
+    let res = Query::parse_path("evm/8453/base_fee");
+
+    assert!(matches!(
+        res,
+        Ok(Query::Evm(EvmQuery::ChainStatus {
+            chain_id,
+            metric: ChainMetric::BaseFee,
+        })) if chain_id == U256::from(8453_u16)
+    ));
+}
+
+#[test]
+fn test_parsing_valid_evm_pending_tx_count_query() {
+    let res =
+        Query::parse_path("evm/1/tx_count/0xd8dA6BF26964aF9D7eEd9e03E53415D37aA96045/pending");
+
+    assert!(matches!(
+        res,
+        Ok(Query::Evm(EvmQuery::TxCount {
+            chain_id,
+            address,
+            pending: true,
+        })) if chain_id == U256::from(1_u8) &&
+              address == address!("d8dA6BF26964aF9D7eEd9e03E53415D37aA96045")
+    ));
+}
+
+#[test]
+fn test_parsing_valid_bitcoin_tx_count_query() {
+    let res = Query::parse_path("btc/mainnet/tx_count/bc1qxy2kgdygjrsqtzq2n0yrf2493p83kkfjhx0wlh");
+
+    assert!(matches!(
+        res,
+        Ok(Query::Bitcoin(BitcoinQuery::TxCount {
+            network: BitcoinNetwork::Mainnet,
+            address,
+        })) if address == "bc1qxy2kgdygjrsqtzq2n0yrf2493p83kkfjhx0wlh"
+    ));
+}
+
+#[test]
+fn test_parsing_valid_evm_erc20_flow_query() {
+    let res = Query::parse_path("evm/1/erc20_flow/0xdac17f958d2ee523a2206206994597c13d831ec7/0xd8dA6BF26964aF9D7eEd9e03E53415D37aA96045/net/7d");
+
+    assert!(matches!(
+        res,
+        Ok(Query::Evm(EvmQuery::ERC20Flow {
+            chain_id,
+            contract_address,
+            address,
+            metric: FlowMetric::Net,
+            window: BlockWindow::Seconds(604_800),
+        })) if chain_id == U256::from(1_u8) &&
+               contract_address == address!("dac17f958d2ee523a2206206994597c13d831ec7") &&
+              address == address!("d8dA6BF26964aF9D7eEd9e03E53415D37aA96045")
+    ));
+}
+
+#[test]
+fn test_parsing_evm_erc20_flow_query_with_block_window() {
+    let res = Query::parse_path("evm/1/erc20_flow/0xdac17f958d2ee523a2206206994597c13d831ec7/0xd8dA6BF26964aF9D7eEd9e03E53415D37aA96045/count/5000b");
+
+    assert!(matches!(
+        res,
+        Ok(Query::Evm(EvmQuery::ERC20Flow {
+            metric: FlowMetric::Count,
+            window: BlockWindow::Blocks(5000),
+            ..
+        }))
+    ));
+}
+
+#[test]
+fn test_parsing_evm_erc20_flow_query_with_bad_window() {
+    let res = Query::parse_path("evm/1/erc20_flow/0xdac17f958d2ee523a2206206994597c13d831ec7/0xd8dA6BF26964aF9D7eEd9e03E53415D37aA96045/inflow/7w");
+
+    assert!(matches!(
+        res,
+        Err(QueryParseError {
+            kind: QueryParseErrorKind::Evm(EvmQueryParseError::BadWindow),
+            ..
+        })
+    ));
+}
+
+#[test]
+fn test_parsing_evm_erc20_flow_query_with_overflowing_window() {
+    let res = Query::parse_path("evm/1/erc20_flow/0xdac17f958d2ee523a2206206994597c13d831ec7/0xd8dA6BF26964aF9D7eEd9e03E53415D37aA96045/inflow/99999999999999999d");
+
+    assert!(matches!(
+        res,
+        Err(QueryParseError {
+            kind: QueryParseErrorKind::Evm(EvmQueryParseError::BadWindow),
+            ..
+        })
+    ));
+}
+
+#[test]
+fn test_parsing_evm_erc20_flow_query_with_too_large_window() {
+    for window in ["36500d", "100000000b", "2161h"] {
+        let res = Query::parse_path(&format!("evm/1/erc20_flow/0xdac17f958d2ee523a2206206994597c13d831ec7/0xd8dA6BF26964aF9D7eEd9e03E53415D37aA96045/inflow/{window}"));
+
+        assert!(matches!(
+            res,
+            Err(QueryParseError {
+                kind: QueryParseErrorKind::Evm(EvmQueryParseError::BadWindow),
+                ..
+            })
+        ));
+    }
+
+    assert!(Query::parse_path("evm/1/erc20_flow/0xdac17f958d2ee523a2206206994597c13d831ec7/0xd8dA6BF26964aF9D7eEd9e03E53415D37aA96045/inflow/90d").is_ok());
+}
+
+#[test]
+fn test_parsing_valid_evm_erc20_holders_query() {
+    let res = Query::parse_path("evm/1/erc20_holders/0xdac17f958d2ee523a2206206994597c13d831ec7");
+
+    assert!(matches!(
+        res,
+        Ok(Query::Evm(EvmQuery::ERC20Holders {
+            chain_id,
+            contract_address,
+        })) if chain_id == U256::from(1_u8) &&
+               contract_address == address!("dac17f958d2ee523a2206206994597c13d831ec7")
+    ));
+}
+
+#[test]
+fn test_parse_error_points_at_segment() {
+    let err =
+        Query::parse_path("evm/1/erc20_balance/0xdac17f958d2ee523a2206206994597c13d831ec7/0xnope")
+            .unwrap_err();
+
+    assert_eq!(
+        err.kind,
+        QueryParseErrorKind::Evm(EvmQueryParseError::BadAddress)
+    );
+    assert_eq!(err.segment, 4);
+    assert_eq!(err.text.as_deref(), Some("0xnope"));
+    assert_eq!(
+        err.to_string(),
+        "Invalid address \"0xnope\" at segment 4, expected a 0x-prefixed 20-byte hex address"
+    );
+}
+
+#[test]
+fn test_parse_error_for_missing_segment() {
+    let err =
+        Query::parse_path("evm/1/safe/0xd8dA6BF26964aF9D7eEd9e03E53415D37aA96045").unwrap_err();
+
+    assert_eq!(
+        err.kind,
+        QueryParseErrorKind::Evm(EvmQueryParseError::BadType)
+    );
+    assert_eq!(err.segment, 4);
+    assert_eq!(err.text, None);
+    assert_eq!(err.expected, "threshold, owners or nonce");
+}
+
+#[test]
+fn test_parse_error_for_invalid_source() {
+    let err = Query::parse_path("sol/1/balance").unwrap_err();
+
+    assert_eq!(err.kind, QueryParseErrorKind::InvalidSource);
+    assert_eq!(err.segment, 0);
+    assert_eq!(err.text.as_deref(), Some("sol"));
+}
+
+#[test]
+fn test_parsing_bitcoin_query_without_address() {
+    let err = Query::parse_path("btc/mainnet/balance").unwrap_err();
+
+    assert_eq!(
+        err.kind,
+        QueryParseErrorKind::Bitcoin(BitcoinQueryParseError::BadAddress)
+    );
+    assert_eq!(err.segment, 3);
+}
+
+#[test]
+fn test_parse_error_serialization() {
+    let err = Query::parse_path("evm/mainnet/balance").unwrap_err();
+
+    assert_eq!(
+        serde_json::to_value(&err).unwrap(),
+        serde_json::json!({
+            "error": "bad_chain_id",
+            "segment": 1,
+            "text": "mainnet",
+            "expected": "a numeric chain ID, e.g. 1",
+        })
+    );
+}
+
+#[test]
+fn test_storage_key_is_normalized_path() {
+    let query = Query::parse_path(
+        "EVM/1/ERC20_Flow/0xDAC17F958D2EE523A2206206994597C13D831EC7/0xd8dA6BF26964aF9D7eEd9e03E53415D37aA96045/Net/30D",
+    )
+    .unwrap();
+
+    assert_eq!(
+        query.storage_key(),
+        "evm/1/erc20_flow/0xdac17f958d2ee523a2206206994597c13d831ec7/\
+         0xd8da6bf26964af9d7eed9e03e53415d37aa96045/net/30d"
+    );
+}
+
+#[test]
+fn test_storage_key_parses_back_to_query() {
+    let paths = [
+        "evm/1/balance/0xd8da6bf26964af9d7eed9e03e53415d37aa96045",
+        "evm/1/safe/0xd8da6bf26964af9d7eed9e03e53415d37aa96045/owners",
+        "evm/1/ens_expiry/vitalik.eth",
+        "evm/1/proxy/0xd8da6bf26964af9d7eed9e03e53415d37aa96045/admin",
+        "evm/1/storage/0xd8da6bf26964af9d7eed9e03e53415d37aa96045/0x2a/address",
+        "evm/10/gas_price",
+        "evm/1/tx_count/0xd8da6bf26964af9d7eed9e03e53415d37aa96045/pending",
+        "evm/1/erc20_holders/0xdac17f958d2ee523a2206206994597c13d831ec7",
+        "btc/signet/tx_count/tb1qw508d6qejxtdg4y5r3zarvary0c5xw7kxpjzsx",
+    ];
+
+    for path in paths {
+        let query = Query::parse_path(path).unwrap();
+
+        assert_eq!(query.storage_key(), path);
+        assert_eq!(
+            Query::parse_path(&query.storage_key()).unwrap().cache_key(),
+            query.cache_key()
+        );
+    }
+}
@@ -0,0 +1,73 @@
+use badgify_rs::{
+    data_source::evm_quorum::{is_decided, tally, Verifiable},
+    error::BadgifyError,
+};
+
+#[derive(Debug, PartialEq)]
+struct Value(u64);
+
+impl Verifiable for Value {
+    fn agrees_with(&self, other: &Self) -> bool {
+        self == other
+    }
+
+    fn describe(&self) -> String {
+        self.0.to_string()
+    }
+}
+
+fn responses(values: &[(&str, u64)]) -> Vec<(String, Value)> {
+    values
+        .iter()
+        .map(|(url, value)| (url.to_string(), Value(*value)))
+        .collect()
+}
+
+#[test]
+fn test_tally_returns_agreed_value() {
+    let result = tally(responses(&[("a", 5), ("b", 7), ("c", 5)]), 2, 100, None);
+
+    assert_eq!(result.unwrap(), Value(5));
+}
+
+#[test]
+fn test_tally_reports_disagreement() {
+    let Err(BadgifyError::Quorum(err)) =
+        tally(responses(&[("a", 5), ("b", 7), ("c", 9)]), 2, 100, None)
+    else {
+        panic!("expected a quorum error");
+    };
+
+    assert_eq!(err.block_number, 100);
+    assert_eq!(err.responses.len(), 3);
+    assert_eq!(
+        err.to_string(),
+        "RPC endpoints disagree at block 100, 2 must agree: 5 from a; 7 from b; 9 from c;"
+    );
+}
+
+#[test]
+fn test_tally_fails_without_enough_responses() {
+    // Endpoints that fail are not a disagreement.
+    let failure = BadgifyError::Upstream("header not found".to_string());
+    let err = tally(responses(&[("a", 5)]), 2, 100, Some(failure)).unwrap_err();
+    assert!(matches!(err, BadgifyError::Upstream(_)));
+    assert!(err.is_endpoint_failure());
+
+    let err = tally(responses(&[]), 2, 100, None).unwrap_err();
+    assert!(matches!(err, BadgifyError::NoRpc(_)));
+}
+
+#[test]
+fn test_decided_once_quorum_reached() {
+    assert!(is_decided(&responses(&[("a", 5), ("b", 5)]), 2, 1));
+    assert!(!is_decided(&responses(&[("a", 5)]), 2, 1));
+    assert!(!is_decided(&responses(&[]), 2, 3));
+}
+
+#[test]
+fn test_decided_once_quorum_unreachable() {
+    assert!(is_decided(&responses(&[("a", 5), ("b", 7)]), 3, 1));
+    assert!(!is_decided(&responses(&[("a", 5), ("b", 7)]), 3, 2));
+    assert!(is_decided(&responses(&[]), 2, 1));
+}
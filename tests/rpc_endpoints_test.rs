@@ -0,0 +1,72 @@
+use badgify_rs::{config::Config, data_source::rpc_endpoints::RpcEndpoints, types::ChainID};
+
+fn config() -> Config {
+    r#"
+        [[rpc.endpoints]]
+        chain_id = 1
+        urls = ["https://eth-mainnet.g.alchemy.com/v2/${ALCHEMY_KEY}", "http://localhost:8545"]
+
+        [[rpc.endpoints]]
+        chain_id = 10
+        urls = ["https://optimism-mainnet.infura.io/v3/${INFURA_KEY}", "wss://optimism-mainnet.infura.io/ws/v3/${INFURA_KEY}"]
+        use_chainlist = false
+        websocket = true
+    "#
+    .parse()
+    .unwrap()
+}
+
+fn lookup(name: &str) -> Option<String> {
+    match name {
+        "ALCHEMY_KEY" => Some("alchemy-secret".to_string()),
+        "INFURA_KEY" => Some("infura-secret".to_string()),
+        _ => None,
+    }
+}
+
+#[test]
+fn test_resolve_fills_placeholders() {
+    let endpoints = RpcEndpoints::resolve(&config().rpc, lookup).unwrap();
+
+    assert_eq!(
+        endpoints.urls(ChainID::from(1)),
+        [
+            "https://eth-mainnet.g.alchemy.com/v2/alchemy-secret",
+            "http://localhost:8545"
+        ]
+    );
+    assert!(endpoints.urls(ChainID::from(137)).is_empty());
+}
+
+#[test]
+fn test_use_chainlist_defaults_to_true() {
+    let endpoints = RpcEndpoints::resolve(&config().rpc, lookup).unwrap();
+
+    assert!(endpoints.use_chainlist(ChainID::from(1)));
+    assert!(!endpoints.use_chainlist(ChainID::from(10)));
+    assert!(endpoints.use_chainlist(ChainID::from(137)));
+}
+
+#[test]
+fn test_resolve_fails_on_missing_secret() {
+    let result = RpcEndpoints::resolve(&config().rpc, |_| None);
+
+    assert!(result.is_err());
+}
+
+#[test]
+fn test_redact_hides_secrets() {
+    let endpoints = RpcEndpoints::resolve(&config().rpc, lookup).unwrap();
+
+    assert_eq!(
+        endpoints.redact("https://optimism-mainnet.infura.io/v3/infura-secret"),
+        "https://optimism-mainnet.infura.io/v3/${INFURA_KEY}"
+    );
+}
+
+#[test]
+fn test_websocket_chains() {
+    let endpoints = RpcEndpoints::resolve(&config().rpc, lookup).unwrap();
+
+    assert_eq!(endpoints.websocket_chains(), vec![ChainID::from(10)]);
+}
@@ -0,0 +1,119 @@
+use std::time::Duration;
+
+use badgify_rs::{config::RpcConfig, data_source::rpc_health::RpcHealth, types::ChainID};
+
+fn urls(urls: &[&str]) -> Vec<String> {
+    urls.iter().map(|url| url.to_string()).collect()
+}
+
+#[test]
+fn test_rank_keeps_order_of_unknown_endpoints() {
+    let health = RpcHealth::new(&RpcConfig::default());
+
+    assert_eq!(
+        health.rank(ChainID::from(1), urls(&["https://a", "https://b"])),
+        urls(&["https://a", "https://b"])
+    );
+}
+
+#[test]
+fn test_rank_prefers_faster_endpoints() {
+    let health = RpcHealth::new(&RpcConfig::default());
+    let chain_id = ChainID::from(1);
+
+    health.record_success(
+        chain_id,
+        "https://slow",
+        Some(Duration::from_millis(900)),
+        None,
+    );
+    health.record_success(
+        chain_id,
+        "https://fast",
+        Some(Duration::from_millis(50)),
+        None,
+    );
+
+    assert_eq!(
+        health.rank(
+            chain_id,
+            urls(&["https://slow", "https://new", "https://fast"])
+        ),
+        urls(&["https://fast", "https://new", "https://slow"])
+    );
+}
+
+#[test]
+fn test_rank_puts_lagging_endpoints_last() {
+    let health = RpcHealth::new(&RpcConfig::default());
+    let chain_id = ChainID::from(1);
+
+    health.record_success(
+        chain_id,
+        "https://stale",
+        Some(Duration::from_millis(10)),
+        Some(100),
+    );
+    health.record_success(
+        chain_id,
+        "https://synced",
+        Some(Duration::from_millis(800)),
+        Some(200),
+    );
+
+    assert_eq!(
+        health.rank(chain_id, urls(&["https://stale", "https://synced"])),
+        urls(&["https://synced", "https://stale"])
+    );
+}
+
+#[test]
+fn test_failing_endpoint_is_ejected() {
+    let health = RpcHealth::new(&RpcConfig::default());
+    let chain_id = ChainID::from(1);
+
+    health.record_failure(chain_id, "https://down");
+    health.record_failure(chain_id, "https://down");
+    assert!(health.is_available(chain_id, "https://down"));
+
+    health.record_failure(chain_id, "https://down");
+    assert!(!health.is_available(chain_id, "https://down"));
+    assert_eq!(
+        health.rank(chain_id, urls(&["https://down", "https://up"])),
+        urls(&["https://up"])
+    );
+
+    let report = health.report();
+    assert_eq!(report["1"][0].failures, 3);
+    assert!(report["1"][0].ejected_for_secs.is_some());
+}
+
+#[test]
+fn test_ejected_endpoints_are_used_when_nothing_else_is_left() {
+    let health = RpcHealth::new(&RpcConfig {
+        failure_threshold: 1,
+        ..Default::default()
+    });
+    let chain_id = ChainID::from(1);
+
+    health.record_failure(chain_id, "https://down");
+
+    assert_eq!(
+        health.rank(chain_id, urls(&["https://down"])),
+        urls(&["https://down"])
+    );
+}
+
+#[test]
+fn test_endpoint_returns_after_cool_down() {
+    let health = RpcHealth::new(&RpcConfig {
+        failure_threshold: 1,
+        cooldown_secs: 0,
+        ..Default::default()
+    });
+    let chain_id = ChainID::from(1);
+
+    health.record_failure(chain_id, "https://flaky");
+
+    assert!(health.is_available(chain_id, "https://flaky"));
+}
@@ -0,0 +1,126 @@
+use std::{path::PathBuf, sync::Arc};
+
+use alloy::primitives::{address, I256, U256};
+use badgify_rs::{
+    config::CacheConfig,
+    data_source::{stale_store::StaleStore, SourceResponse},
+};
+
+fn config(stale_max_age_secs: u64, stale_database_path: Option<PathBuf>) -> CacheConfig {
+    CacheConfig {
+        stale_max_age_secs,
+        stale_database_path,
+        ..CacheConfig::default()
+    }
+}
+
+#[tokio::test]
+async fn test_serves_last_result_up_to_max_age() {
+    let store = StaleStore::<u64>::open(&config(100, None)).unwrap();
+
+    store.record("a", Arc::new(1), 1_000);
+    store.record("a", Arc::new(2), 1_050);
+
+    let stale = store.get("a", 1_150).await.unwrap();
+    assert_eq!((*stale.result, stale.fetched_at), (2, 1_050));
+    assert!(store.get("a", 1_151).await.is_none());
+    assert!(store.get("b", 1_050).await.is_none());
+}
+
+#[tokio::test]
+async fn test_zero_max_age_disables_stale_results() {
+    let store = StaleStore::<u64>::open(&config(0, None)).unwrap();
+
+    store.record("a", Arc::new(1), 1_000);
+
+    assert!(store.get("a", 1_000).await.is_none());
+}
+
+#[tokio::test]
+async fn test_persisted_results_survive_reopening() {
+    let path = std::env::temp_dir().join(format!("badgify_stale_{}.sqlite", std::process::id()));
+    let _ = std::fs::remove_file(&path);
+
+    let store = StaleStore::<SourceResponse>::open(&config(100, Some(path.clone()))).unwrap();
+    store.record(
+        "a",
+        Arc::new(SourceResponse::Decimal {
+            value: U256::from(1_500_000),
+            decimals: 6,
+        }),
+        1_000,
+    );
+    drop(store);
+
+    let store = StaleStore::<SourceResponse>::open(&config(100, Some(path.clone()))).unwrap();
+    let stale = store.get("a", 1_010).await.unwrap();
+    std::fs::remove_file(&path).unwrap();
+
+    assert_eq!(stale.fetched_at, 1_000);
+    assert_eq!(
+        *stale.result,
+        SourceResponse::Decimal {
+            value: U256::from(1_500_000),
+            decimals: 6,
+        }
+    );
+}
+
+#[tokio::test]
+async fn test_persisted_writes_keep_latest_result() {
+    let path = std::env::temp_dir().join(format!(
+        "badgify_stale_latest_{}.sqlite",
+        std::process::id()
+    ));
+    let _ = std::fs::remove_file(&path);
+
+    let store = StaleStore::<u64>::open(&config(100, Some(path.clone()))).unwrap();
+    for fetched_at in 1_000..1_100 {
+        store.record("a", Arc::new(fetched_at), fetched_at);
+        store.record("b", Arc::new(7), fetched_at);
+    }
+    // Dropping the store waits for the writer to catch up.
+    drop(store);
+
+    let store = StaleStore::<u64>::open(&config(100, Some(path.clone()))).unwrap();
+    let a = store.get("a", 1_100).await.unwrap();
+    let b = store.get("b", 1_100).await.unwrap();
+    drop(store);
+    std::fs::remove_file(&path).unwrap();
+
+    assert_eq!((*a.result, a.fetched_at), (1_099, 1_099));
+    assert_eq!((*b.result, b.fetched_at), (7, 1_099));
+}
+
+#[test]
+fn test_source_responses_round_trip_through_json() {
+    let responses = [
+        SourceResponse::SignedDecimal {
+            value: I256::try_from(-42).unwrap(),
+            decimals: 2,
+        },
+        SourceResponse::Integer {
+            value: U256::from(12_345),
+        },
+        SourceResponse::AlphaNumeric {
+            value: "vitalik.eth".to_string(),
+        },
+        SourceResponse::Address {
+            value: address!("d8dA6BF26964aF9D7eEd9e03E53415D37aA96045"),
+        },
+        SourceResponse::Boolean { value: true },
+        SourceResponse::Duration { seconds: 90 },
+        SourceResponse::Timestamp {
+            value: 1_700_000_000,
+        },
+    ];
+
+    for response in responses {
+        let json = serde_json::to_string(&response).unwrap();
+
+        assert_eq!(
+            serde_json::from_str::<SourceResponse>(&json).unwrap(),
+            response
+        );
+    }
+}